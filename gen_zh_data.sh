@@ -0,0 +1,52 @@
+#!/bin/bash
+
+# Generates the pinyin and Traditional to Simplified Chinese tables of
+# src-tauri/src/utils/zh.rs with ICU's transliterators, needs `uconv` from
+# icu-devtools.
+
+set -e
+
+OUT_DIR="./src-tauri/src/utils/zh"
+mkdir -p "$OUT_DIR"
+
+python3 - "$OUT_DIR" <<'PY'
+import subprocess
+import sys
+import unicodedata
+
+out_dir = sys.argv[1]
+# CJK Unified Ideographs and Extension A.
+chars = [chr(c) for c in [*range(0x3400, 0x4DC0), *range(0x4E00, 0xA000)]]
+text = "\n".join(chars) + "\n"
+
+
+def transliterate(rule):
+    result = subprocess.run(
+        ["uconv", "-x", rule], input=text, capture_output=True, text=True, check=True
+    )
+    return result.stdout.splitlines()
+
+
+def toneless(syllable):
+    # NFD splits ü into u and a combining diaeresis.
+    syllable = unicodedata.normalize("NFD", syllable).replace("u\u0308", "v")
+    return "".join(c for c in syllable if not unicodedata.combining(c))
+
+
+syllables = {}
+for c, latin in zip(chars, transliterate("Han-Latin")):
+    latin = toneless(latin.strip())
+    if latin != c and latin.isascii() and latin.isalpha():
+        syllables.setdefault(latin.lower(), []).append(c)
+
+with open(f"{out_dir}/pinyin.txt", "w") as f:
+    for syllable in sorted(syllables):
+        f.write(f"{syllable} {''.join(syllables[syllable])}\n")
+
+with open(f"{out_dir}/t2s.txt", "w") as f:
+    for c, simplified in zip(chars, transliterate("Traditional-Simplified")):
+        if simplified != c and len(simplified) == 1:
+            f.write(f"{c}{simplified}\n")
+PY
+
+echo "Chinese tables generated at: $OUT_DIR"
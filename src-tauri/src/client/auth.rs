@@ -0,0 +1,61 @@
+use base64::{Engine, engine::general_purpose::URL_SAFE_NO_PAD};
+use chrono::{DateTime, Utc};
+use reqwest::StatusCode;
+use serde::{Deserialize, Serialize};
+
+use crate::client::ApiError;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum AuthFailure {
+    /// 401, the token expired or was revoked. Every request will fail until
+    /// the user logs in again.
+    Unauthenticated,
+    /// 403, the token is fine but may not touch this resource.
+    PermissionDenied,
+}
+
+impl ApiError {
+    pub fn auth_failure(&self) -> Option<AuthFailure> {
+        match self.status()? {
+            StatusCode::UNAUTHORIZED => Some(AuthFailure::Unauthenticated),
+            StatusCode::FORBIDDEN => Some(AuthFailure::PermissionDenied),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct Claims {
+    exp: Option<i64>,
+}
+
+/// Reads the `exp` claim of a JWT access token. The signature is not checked,
+/// the server does that, this is only to warn before the token runs out.
+/// Returns `None` for tokens that never expire or are not JWTs.
+pub fn token_expiry(token: &str) -> Option<DateTime<Utc>> {
+    let payload = token.split('.').nth(1)?;
+    let payload = URL_SAFE_NO_PAD.decode(payload.trim_end_matches('=')).ok()?;
+    let claims: Claims = serde_json::from_slice(&payload).ok()?;
+
+    DateTime::from_timestamp(claims.exp?, 0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_token_expiry() {
+        let claims = URL_SAFE_NO_PAD.encode(r#"{"name":"","exp":1767225600,"iss":"memos"}"#);
+        let token = format!("eyJhbGciOiJIUzI1NiJ9.{}.c2ln", claims);
+        assert_eq!(
+            token_expiry(&token),
+            DateTime::from_timestamp(1767225600, 0)
+        );
+
+        let claims = URL_SAFE_NO_PAD.encode(r#"{"name":"","iss":"memos"}"#);
+        assert_eq!(token_expiry(&format!("a.{}.b", claims)), None);
+        assert_eq!(token_expiry("not a jwt"), None);
+    }
+}
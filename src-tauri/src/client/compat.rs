@@ -0,0 +1,247 @@
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// Filter identifiers Memos 0.25 renamed along with resources.
+const ATTACHMENT_IDENTIFIERS: &[(&str, &str)] = &[("has_resource", "has_attachment")];
+
+/// Version reported by `WorkspaceService.GetWorkspaceProfile`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub struct ServerVersion {
+    pub major: u32,
+    pub minor: u32,
+    pub patch: u32,
+}
+
+impl ServerVersion {
+    /// The version the generated models were built from.
+    pub const BASELINE: ServerVersion = ServerVersion::new(0, 24, 0);
+
+    pub const fn new(major: u32, minor: u32, patch: u32) -> Self {
+        Self {
+            major,
+            minor,
+            patch,
+        }
+    }
+
+    /// Accepts `0.24.4`, `v0.25.0` and pre-release suffixes like `0.25.0-rc1`.
+    pub fn parse(version: &str) -> Option<Self> {
+        let version = version.trim().trim_start_matches('v');
+        let version = version.split(['-', '+']).next()?;
+        let mut parts = version.split('.').map(|x| x.parse::<u32>());
+
+        let major = parts.next()?.ok()?;
+        let minor = parts.next().unwrap_or(Ok(0)).ok()?;
+        let patch = parts.next().unwrap_or(Ok(0)).ok()?;
+
+        Some(Self::new(major, minor, patch))
+    }
+}
+
+impl fmt::Display for ServerVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{}.{}", self.major, self.minor, self.patch)
+    }
+}
+
+/// API differences between the server a client talks to and the 0.24 shapes
+/// of the generated models. Requests and responses are rewritten at the JSON
+/// level so the rest of the crate only ever sees the 0.24 models.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ServerCompat {
+    /// Memos 0.25 renamed resources to attachments, in paths and memo fields.
+    pub attachments: bool,
+    /// Memos 0.25 replaced the `sort`/`direction` list parameters with `orderBy`.
+    pub order_by: bool,
+    /// Memos 0.26 moved `workspace/*` endpoints to `instance/*`.
+    pub instance: bool,
+}
+
+impl ServerCompat {
+    pub fn from_version(version: ServerVersion) -> Self {
+        Self {
+            attachments: version >= ServerVersion::new(0, 25, 0),
+            order_by: version >= ServerVersion::new(0, 25, 0),
+            instance: version >= ServerVersion::new(0, 26, 0),
+        }
+    }
+
+    pub fn is_baseline(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// Maps a 0.24 api path (without the `/api/v1/` prefix) to the server's.
+    pub fn adapt_path(&self, path: &str) -> String {
+        let mut segments = path.split('/').map(str::to_string).collect::<Vec<_>>();
+        for (i, segment) in segments.iter_mut().enumerate() {
+            if self.attachments && segment == "resources" {
+                *segment = "attachments".to_string();
+            }
+            if self.instance && i == 0 && segment == "workspace" {
+                *segment = "instance".to_string();
+            }
+        }
+        segments.join("/")
+    }
+
+    /// Rewrites a request body from 0.24 field names to the server's.
+    pub fn adapt_outgoing(&self, value: &mut Value) {
+        if self.attachments {
+            rename_keys(value, "resources", "attachments");
+        }
+    }
+
+    /// Rewrites a response body from the server's field names to 0.24.
+    pub fn adapt_incoming(&self, value: &mut Value) {
+        if self.attachments {
+            rename_keys(value, "attachments", "resources");
+        }
+    }
+
+    /// Rewrites `ListMemos` query parameters.
+    pub fn adapt_list_query(&self, query: &mut Value) {
+        let Some(query) = query.as_object_mut() else {
+            return;
+        };
+
+        if self.order_by {
+            let sort = query.remove("sort");
+            let direction = query.remove("direction");
+            if let Some(Value::String(sort)) = sort {
+                let direction = match direction {
+                    Some(Value::String(d)) if d == "ASC" => "asc",
+                    _ => "desc",
+                };
+                query.insert(
+                    "orderBy".to_string(),
+                    Value::String(format!("{} {}", sort, direction)),
+                );
+            }
+        }
+
+        if let Some(Value::String(filter)) = query.get_mut("filter") {
+            *filter = self.adapt_filter(filter);
+        }
+    }
+
+    /// Renames whole filter identifiers, leaving string literals and other
+    /// identifiers that merely contain a renamed one untouched.
+    pub fn adapt_filter(&self, filter: &str) -> String {
+        if !self.attachments {
+            return filter.to_string();
+        }
+
+        let mut result = String::with_capacity(filter.len());
+        let mut chars = filter.chars().peekable();
+        while let Some(c) = chars.next() {
+            if c == '"' || c == '\'' {
+                result.push(c);
+                while let Some(x) = chars.next() {
+                    result.push(x);
+                    if x == '\\' {
+                        if let Some(escaped) = chars.next() {
+                            result.push(escaped);
+                        }
+                    } else if x == c {
+                        break;
+                    }
+                }
+            } else if c.is_alphabetic() || c == '_' {
+                let mut ident = c.to_string();
+                while let Some(&x) = chars.peek() {
+                    if x.is_alphanumeric() || x == '_' {
+                        ident.push(x);
+                        chars.next();
+                    } else {
+                        break;
+                    }
+                }
+                let renamed = ATTACHMENT_IDENTIFIERS
+                    .iter()
+                    .find(|(from, _)| *from == ident)
+                    .map_or(ident.as_str(), |(_, to)| to);
+                result.push_str(renamed);
+            } else {
+                result.push(c);
+            }
+        }
+
+        result
+    }
+}
+
+fn rename_keys(value: &mut Value, from: &str, to: &str) {
+    match value {
+        Value::Object(map) => {
+            if let Some(v) = map.remove(from) {
+                map.insert(to.to_string(), v);
+            }
+            for v in map.values_mut() {
+                rename_keys(v, from, to);
+            }
+        }
+        Value::Array(list) => {
+            for v in list.iter_mut() {
+                rename_keys(v, from, to);
+            }
+        }
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_version() {
+        assert_eq!(
+            ServerVersion::parse("0.24.4"),
+            Some(ServerVersion::new(0, 24, 4))
+        );
+        assert_eq!(
+            ServerVersion::parse("v0.25.0-rc1"),
+            Some(ServerVersion::new(0, 25, 0))
+        );
+        assert_eq!(
+            ServerVersion::parse("0.26"),
+            Some(ServerVersion::new(0, 26, 0))
+        );
+        assert_eq!(ServerVersion::parse("dev"), None);
+    }
+
+    #[test]
+    fn test_adapt() {
+        let compat = ServerCompat::from_version(ServerVersion::new(0, 26, 1));
+
+        assert_eq!(
+            compat.adapt_path("memos/1/resources"),
+            "memos/1/attachments"
+        );
+        assert_eq!(compat.adapt_path("workspace/profile"), "instance/profile");
+        assert_eq!(
+            compat.adapt_filter(r#"has_resource && content.contains("resource")"#),
+            r#"has_attachment && content.contains("resource")"#
+        );
+        assert_eq!(
+            compat.adapt_filter("resource_count > 0 || my_has_resource"),
+            "resource_count > 0 || my_has_resource"
+        );
+
+        let mut memo = serde_json::json!({"memos": [{"name": "memos/1", "attachments": []}]});
+        compat.adapt_incoming(&mut memo);
+        assert_eq!(
+            memo,
+            serde_json::json!({"memos": [{"name": "memos/1", "resources": []}]})
+        );
+
+        let mut query = serde_json::json!({"sort": "update_time", "direction": "ASC"});
+        compat.adapt_list_query(&mut query);
+        assert_eq!(query, serde_json::json!({"orderBy": "update_time asc"}));
+
+        assert!(ServerCompat::from_version(ServerVersion::BASELINE).is_baseline());
+    }
+}
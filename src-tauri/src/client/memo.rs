@@ -0,0 +1,138 @@
+use serde::Serialize;
+
+use crate::{
+    api::models::{
+        Apiv1Memo, MemoServiceSetMemoRelationsBody, MemoServiceSetMemoResourcesBody,
+        TheMemoToUpdateTheNameFieldIsRequired, V1Direction, V1ListMemoCommentsResponse,
+        V1ListMemoRelationsResponse, V1ListMemoResourcesResponse, V1ListMemosResponse,
+        V1MemoRelation, V1Resource, V1State,
+    },
+    client::{ApiError, MemosClient},
+};
+
+pub const DEFAULT_PAGE_SIZE: i32 = 30;
+
+/// Query of `MemoService.ListMemos`, serialized as url query parameters.
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ListMemosRequest {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub parent: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub page_size: Option<i32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub page_token: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub state: Option<V1State>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sort: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub direction: Option<V1Direction>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub filter: Option<String>,
+}
+
+impl MemosClient {
+    pub async fn list_memos(
+        &self,
+        request: &ListMemosRequest,
+    ) -> Result<V1ListMemosResponse, ApiError> {
+        let mut query = serde_json::to_value(request)
+            .map_err(|e| ApiError::InvalidConfig(format!("Failed to serialize query: {}", e)))?;
+        self.compat.adapt_list_query(&mut query);
+        self.get("memos", &query).await
+    }
+
+    /// Follows `nextPageToken` until the server reports no more pages.
+    pub async fn list_all_memos(
+        &self,
+        request: &ListMemosRequest,
+    ) -> Result<Vec<Apiv1Memo>, ApiError> {
+        let mut request = request.clone();
+        if request.page_size.is_none() {
+            request.page_size = Some(DEFAULT_PAGE_SIZE);
+        }
+
+        let mut memos = Vec::new();
+        loop {
+            let response = self.list_memos(&request).await?;
+            memos.extend(response.memos.unwrap_or_default());
+
+            match response.next_page_token {
+                Some(token) if !token.is_empty() => request.page_token = Some(token),
+                _ => break,
+            }
+        }
+
+        Ok(memos)
+    }
+
+    pub async fn get_memo(&self, name: &str) -> Result<Apiv1Memo, ApiError> {
+        self.get(name, &()).await
+    }
+
+    pub async fn create_memo(&self, memo: &Apiv1Memo) -> Result<Apiv1Memo, ApiError> {
+        self.post("memos", memo).await
+    }
+
+    /// Only the fields set in `memo` are updated, the server derives the update
+    /// mask from the request body.
+    pub async fn update_memo(
+        &self,
+        name: &str,
+        memo: &TheMemoToUpdateTheNameFieldIsRequired,
+    ) -> Result<Apiv1Memo, ApiError> {
+        self.patch(name, memo).await
+    }
+
+    pub async fn delete_memo(&self, name: &str) -> Result<(), ApiError> {
+        self.delete(name, &()).await
+    }
+
+    pub async fn list_memo_comments(
+        &self,
+        name: &str,
+    ) -> Result<V1ListMemoCommentsResponse, ApiError> {
+        self.get(&format!("{}/comments", name), &()).await
+    }
+
+    pub async fn list_memo_resources(
+        &self,
+        name: &str,
+    ) -> Result<V1ListMemoResourcesResponse, ApiError> {
+        self.get(&format!("{}/resources", name), &()).await
+    }
+
+    pub async fn set_memo_resources(
+        &self,
+        name: &str,
+        resources: Vec<V1Resource>,
+    ) -> Result<(), ApiError> {
+        let body = MemoServiceSetMemoResourcesBody {
+            resources: Some(resources),
+        };
+        self.patch::<serde_json::Value, _>(&format!("{}/resources", name), &body)
+            .await
+            .map(|_| ())
+    }
+
+    pub async fn list_memo_relations(
+        &self,
+        name: &str,
+    ) -> Result<V1ListMemoRelationsResponse, ApiError> {
+        self.get(&format!("{}/relations", name), &()).await
+    }
+
+    pub async fn set_memo_relations(
+        &self,
+        name: &str,
+        relations: Vec<V1MemoRelation>,
+    ) -> Result<(), ApiError> {
+        let body = MemoServiceSetMemoRelationsBody {
+            relations: Some(relations),
+        };
+        self.patch::<serde_json::Value, _>(&format!("{}/relations", name), &body)
+            .await
+            .map(|_| ())
+    }
+}
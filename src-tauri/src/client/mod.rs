@@ -0,0 +1,206 @@
+use std::{fmt, time::Duration};
+
+use reqwest::{Method, RequestBuilder, StatusCode};
+use serde::{Serialize, de::DeserializeOwned};
+
+use crate::{
+    api::models::GooglerpcStatus,
+    client::compat::{ServerCompat, ServerVersion},
+};
+
+pub mod auth;
+pub mod compat;
+pub mod memo;
+pub mod resource;
+pub mod shortcut;
+pub mod tag;
+pub mod user;
+pub mod workspace;
+
+pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Debug)]
+pub enum ApiError {
+    /// The request could not be sent or no response arrived in time.
+    Network(reqwest::Error),
+    /// The server answered with a non-success status code.
+    Status {
+        code: StatusCode,
+        message: String,
+    },
+    /// The response body is not what the generated models expect.
+    Decode(String),
+    InvalidConfig(String),
+}
+
+impl ApiError {
+    pub fn is_timeout(&self) -> bool {
+        matches!(self, ApiError::Network(e) if e.is_timeout())
+    }
+
+    pub fn status(&self) -> Option<StatusCode> {
+        match self {
+            ApiError::Status { code, .. } => Some(*code),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for ApiError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ApiError::Network(e) if e.is_timeout() => write!(f, "Connection timeout"),
+            ApiError::Network(e) => write!(f, "Network error: {}", e),
+            ApiError::Status { code, message } => write!(f, "HTTP error: {}, {}", code, message),
+            ApiError::Decode(e) => write!(f, "Failed to decode response: {}", e),
+            ApiError::InvalidConfig(e) => write!(f, "Invalid client config: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for ApiError {}
+
+impl From<ApiError> for String {
+    fn from(e: ApiError) -> Self {
+        e.to_string()
+    }
+}
+
+/// Typed client for the Memos v1 HTTP API.
+///
+/// The service methods live in the submodules, one per Memos service. Servers
+/// newer than 0.24 are spoken to through [`ServerCompat`], set it with
+/// [`MemosClient::with_server_version`].
+#[derive(Debug, Clone)]
+pub struct MemosClient {
+    http: reqwest::Client,
+    server_url: String,
+    access_token: String,
+    compat: ServerCompat,
+}
+
+impl MemosClient {
+    pub fn new(server_url: &str, access_token: &str) -> Result<Self, ApiError> {
+        Self::with_timeout(server_url, access_token, DEFAULT_TIMEOUT)
+    }
+
+    pub fn with_timeout(
+        server_url: &str,
+        access_token: &str,
+        timeout: Duration,
+    ) -> Result<Self, ApiError> {
+        let parsed_url = url::Url::parse(server_url)
+            .map_err(|e| ApiError::InvalidConfig(format!("Invalid server url: {}", e)))?;
+        if parsed_url.scheme() != "http" && parsed_url.scheme() != "https" {
+            return Err(ApiError::InvalidConfig(
+                "Only HTTP and HTTPS server urls are supported".to_string(),
+            ));
+        }
+
+        let http = reqwest::Client::builder()
+            .timeout(timeout)
+            .build()
+            .map_err(|e| ApiError::InvalidConfig(format!("Failed to create HTTP client: {}", e)))?;
+
+        Ok(Self {
+            http,
+            server_url: server_url.trim_end_matches('/').to_string(),
+            access_token: access_token.to_string(),
+            compat: ServerCompat::default(),
+        })
+    }
+
+    pub fn with_server_version(self, version: ServerVersion) -> Self {
+        self.with_compat(ServerCompat::from_version(version))
+    }
+
+    pub fn with_compat(mut self, compat: ServerCompat) -> Self {
+        self.compat = compat;
+        self
+    }
+
+    pub fn server_url(&self) -> &str {
+        &self.server_url
+    }
+
+    pub fn compat(&self) -> ServerCompat {
+        self.compat
+    }
+
+    fn request(&self, method: Method, path: &str) -> RequestBuilder {
+        let url = format!(
+            "{}/api/v1/{}",
+            self.server_url,
+            self.compat.adapt_path(path.trim_start_matches('/'))
+        );
+        self.http
+            .request(method, url)
+            .bearer_auth(&self.access_token)
+    }
+
+    async fn send<T: DeserializeOwned>(&self, request: RequestBuilder) -> Result<T, ApiError> {
+        let response = request.send().await.map_err(ApiError::Network)?;
+
+        let code = response.status();
+        if !code.is_success() {
+            let body = response.text().await.unwrap_or_default();
+            let message = serde_json::from_str::<GooglerpcStatus>(&body)
+                .ok()
+                .and_then(|s| s.message)
+                .unwrap_or(body);
+            return Err(ApiError::Status { code, message });
+        }
+
+        let body = response.bytes().await.map_err(ApiError::Network)?;
+        if self.compat.is_baseline() {
+            return serde_json::from_slice(&body).map_err(|e| ApiError::Decode(e.to_string()));
+        }
+
+        let mut value = serde_json::from_slice::<serde_json::Value>(&body)
+            .map_err(|e| ApiError::Decode(e.to_string()))?;
+        self.compat.adapt_incoming(&mut value);
+        serde_json::from_value(value).map_err(|e| ApiError::Decode(e.to_string()))
+    }
+
+    fn adapt_body<B: Serialize + ?Sized>(&self, body: &B) -> Result<serde_json::Value, ApiError> {
+        let mut value = serde_json::to_value(body)
+            .map_err(|e| ApiError::InvalidConfig(format!("Failed to serialize body: {}", e)))?;
+        self.compat.adapt_outgoing(&mut value);
+        Ok(value)
+    }
+
+    async fn get<T: DeserializeOwned, Q: Serialize + ?Sized>(
+        &self,
+        path: &str,
+        query: &Q,
+    ) -> Result<T, ApiError> {
+        self.send(self.request(Method::GET, path).query(query))
+            .await
+    }
+
+    async fn post<T: DeserializeOwned, B: Serialize + ?Sized>(
+        &self,
+        path: &str,
+        body: &B,
+    ) -> Result<T, ApiError> {
+        let body = self.adapt_body(body)?;
+        self.send(self.request(Method::POST, path).json(&body))
+            .await
+    }
+
+    async fn patch<T: DeserializeOwned, B: Serialize + ?Sized>(
+        &self,
+        path: &str,
+        body: &B,
+    ) -> Result<T, ApiError> {
+        let body = self.adapt_body(body)?;
+        self.send(self.request(Method::PATCH, path).json(&body))
+            .await
+    }
+
+    async fn delete<Q: Serialize + ?Sized>(&self, path: &str, query: &Q) -> Result<(), ApiError> {
+        self.send::<serde_json::Value>(self.request(Method::DELETE, path).query(query))
+            .await
+            .map(|_| ())
+    }
+}
@@ -0,0 +1,31 @@
+use crate::{
+    api::models::{ResourceServiceUpdateResourceRequest, V1ListResourcesResponse, V1Resource},
+    client::{ApiError, MemosClient},
+};
+
+impl MemosClient {
+    pub async fn list_resources(&self) -> Result<Vec<V1Resource>, ApiError> {
+        let response: V1ListResourcesResponse = self.get("resources", &()).await?;
+        Ok(response.resources.unwrap_or_default())
+    }
+
+    pub async fn get_resource(&self, name: &str) -> Result<V1Resource, ApiError> {
+        self.get(name, &()).await
+    }
+
+    pub async fn create_resource(&self, resource: &V1Resource) -> Result<V1Resource, ApiError> {
+        self.post("resources", resource).await
+    }
+
+    pub async fn update_resource(
+        &self,
+        name: &str,
+        resource: &ResourceServiceUpdateResourceRequest,
+    ) -> Result<V1Resource, ApiError> {
+        self.patch(name, resource).await
+    }
+
+    pub async fn delete_resource(&self, name: &str) -> Result<(), ApiError> {
+        self.delete(name, &()).await
+    }
+}
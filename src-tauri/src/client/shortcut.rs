@@ -0,0 +1,37 @@
+use crate::{
+    api::models::{Apiv1Shortcut, ShortcutServiceUpdateShortcutRequest, V1ListShortcutsResponse},
+    client::{ApiError, MemosClient},
+};
+
+impl MemosClient {
+    /// `parent` is the user name, format: users/{id}.
+    pub async fn list_shortcuts(&self, parent: &str) -> Result<Vec<Apiv1Shortcut>, ApiError> {
+        let response: V1ListShortcutsResponse =
+            self.get(&format!("{}/shortcuts", parent), &()).await?;
+        Ok(response.shortcuts.unwrap_or_default())
+    }
+
+    pub async fn create_shortcut(
+        &self,
+        parent: &str,
+        shortcut: &Apiv1Shortcut,
+    ) -> Result<Apiv1Shortcut, ApiError> {
+        self.post(&format!("{}/shortcuts", parent), shortcut).await
+    }
+
+    /// Only the fields set in `shortcut` are updated.
+    pub async fn update_shortcut(
+        &self,
+        parent: &str,
+        id: &str,
+        shortcut: &ShortcutServiceUpdateShortcutRequest,
+    ) -> Result<Apiv1Shortcut, ApiError> {
+        self.patch(&format!("{}/shortcuts/{}", parent, id), shortcut)
+            .await
+    }
+
+    pub async fn delete_shortcut(&self, parent: &str, id: &str) -> Result<(), ApiError> {
+        self.delete(&format!("{}/shortcuts/{}", parent, id), &())
+            .await
+    }
+}
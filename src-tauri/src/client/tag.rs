@@ -0,0 +1,40 @@
+use crate::{
+    api::models::MemoServiceRenameMemoTagBody,
+    client::{ApiError, MemosClient},
+};
+
+/// Tag operations on every memo of the user rather than a single one.
+pub const ALL_MEMOS: &str = "memos/-";
+
+impl MemosClient {
+    /// `parent` is a memo name, or [`ALL_MEMOS`] to rename the tag everywhere.
+    pub async fn rename_tag(
+        &self,
+        parent: &str,
+        old_tag: &str,
+        new_tag: &str,
+    ) -> Result<(), ApiError> {
+        let body = MemoServiceRenameMemoTagBody {
+            old_tag: Some(old_tag.to_string()),
+            new_tag: Some(new_tag.to_string()),
+        };
+        self.patch::<serde_json::Value, _>(&format!("{}/tags:rename", parent), &body)
+            .await
+            .map(|_| ())
+    }
+
+    /// `parent` is a memo name, or [`ALL_MEMOS`] to delete the tag everywhere.
+    pub async fn delete_tag(
+        &self,
+        parent: &str,
+        tag: &str,
+        delete_related_memos: bool,
+    ) -> Result<(), ApiError> {
+        let tag = url::form_urlencoded::byte_serialize(tag.as_bytes()).collect::<String>();
+        self.delete(
+            &format!("{}/tags/{}", parent, tag),
+            &[("deleteRelatedMemos", delete_related_memos)],
+        )
+        .await
+    }
+}
@@ -0,0 +1,32 @@
+use crate::{
+    api::models::{Apiv1UserSetting, UserServiceUpdateUserSettingRequest, V1User, V1UserStats},
+    client::{ApiError, MemosClient},
+};
+
+impl MemosClient {
+    /// Returns the user the access token belongs to, used to verify a login.
+    pub async fn get_auth_status(&self) -> Result<V1User, ApiError> {
+        self.post("auth/status", &serde_json::Map::new()).await
+    }
+
+    pub async fn get_user(&self, name: &str) -> Result<V1User, ApiError> {
+        self.get(name, &()).await
+    }
+
+    pub async fn get_user_stats(&self, name: &str) -> Result<V1UserStats, ApiError> {
+        self.get(&format!("{}/stats", name), &()).await
+    }
+
+    pub async fn get_user_setting(&self, name: &str) -> Result<Apiv1UserSetting, ApiError> {
+        self.get(&format!("{}/setting", name), &()).await
+    }
+
+    /// `name` is the setting name, format: users/{id}/setting.
+    pub async fn update_user_setting(
+        &self,
+        name: &str,
+        setting: &UserServiceUpdateUserSettingRequest,
+    ) -> Result<Apiv1UserSetting, ApiError> {
+        self.patch(name, setting).await
+    }
+}
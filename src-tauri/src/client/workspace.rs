@@ -0,0 +1,44 @@
+use reqwest::StatusCode;
+
+use crate::{
+    api::models::{Apiv1WorkspaceSetting, V1WorkspaceProfile},
+    client::{
+        ApiError, MemosClient,
+        compat::{ServerCompat, ServerVersion},
+    },
+};
+
+impl MemosClient {
+    /// Reads the server version from the workspace profile. Works before the
+    /// version is known: servers that moved the profile to `instance/profile`
+    /// answer the old path with 404.
+    pub async fn probe_server_version(&self) -> Result<ServerVersion, ApiError> {
+        let client = self.clone().with_compat(ServerCompat::default());
+        let profile = match client.get_workspace_profile().await {
+            Err(e) if e.status() == Some(StatusCode::NOT_FOUND) => {
+                let compat = ServerCompat {
+                    instance: true,
+                    ..Default::default()
+                };
+                client.with_compat(compat).get_workspace_profile().await?
+            }
+            r => r?,
+        };
+
+        let version = profile.version.unwrap_or_default();
+        ServerVersion::parse(&version)
+            .ok_or_else(|| ApiError::Decode(format!("Unknown server version: {}", version)))
+    }
+
+    pub async fn get_workspace_profile(&self) -> Result<V1WorkspaceProfile, ApiError> {
+        self.get("workspace/profile", &()).await
+    }
+
+    /// `name` format: settings/{setting}, e.g. `settings/MEMO_RELATED`.
+    pub async fn get_workspace_setting(
+        &self,
+        name: &str,
+    ) -> Result<Apiv1WorkspaceSetting, ApiError> {
+        self.get(&format!("workspace/{}", name), &()).await
+    }
+}
@@ -1,18 +1,22 @@
-use dashmap::DashMap;
 use serde::{Deserialize, Serialize};
-use std::{
-    sync::{Arc, atomic::Ordering},
-    time::Duration,
-};
+use std::{sync::Arc, time::Duration};
 use store::model::{AppState, StoreData};
-use tauri::{AppHandle, Manager, State};
+use tauri::{Manager, State};
 
 use crate::store::{
+    backend::{
+        fs::{FsKvStore, FsMemoStore},
+        sqlite::SqliteMemoStore,
+    },
     memo::{self, persist_memo_cache},
-    model::CacheData,
+    outbox::OutboxData,
+    profile,
 };
-mod api;
-mod store;
+pub mod api;
+pub mod client;
+mod markdown;
+pub mod store;
+pub mod sync;
 mod utils;
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -118,21 +122,19 @@ async fn fetch_link_info(url: String) -> Result<LinkInfo, String> {
 }
 
 #[tauri::command]
-async fn logout(app: AppHandle, state: State<'_, AppState>) -> Result<(), ()> {
+async fn logout(state: State<'_, AppState>) -> Result<(), ()> {
     *state.store.server_url.write() = "".to_string();
     *state.store.user_name.write() = "".to_string();
     state.store.data.clear();
-    if let Err(e) = store::save_store_data(&app, &state.store).await {
+    if let Err(e) = store::save_store_data(&state).await {
         log::error!("Failed to clean store data: {}", e);
     }
 
     // not clear logout user cache data
-    state
-        .cache
-        .is_all_memo_meta_updated
-        .store(false, Ordering::Relaxed);
-    state.cache.all_memo_meta.clear();
-    state.cache.memos.clear();
+    state.cache().clear_meta();
+    state.cache().memos.clear();
+    // queued offline edits stay on disk until the user logs in again
+    *state.outbox().data.write() = OutboxData::default();
     Ok(())
 }
 
@@ -142,43 +144,49 @@ pub fn run() {
         .setup(|app| {
             // TODO: logs is not recorded in setup function
 
-            let store = store::load_store_data(app.handle()).unwrap_or_else(|e| {
+            let kv_store = Arc::new(FsKvStore::new(
+                app.path()
+                    .app_data_dir()
+                    .expect("Failed to get app data dir"),
+            ));
+            let cache_dir = app
+                .path()
+                .app_cache_dir()
+                .expect("Failed to get app cache dir");
+            let memo_store = Arc::new(SqliteMemoStore::open(&cache_dir.join("memos.db"))?);
+            if let Err(e) = memo_store.migrate_from(&FsMemoStore::new(cache_dir)) {
+                log::error!("Failed to migrate memo cache: {}", e);
+            }
+
+            let mut load_errors = Vec::new();
+            let store = store::load_store_data(&*kv_store).unwrap_or_else(|e| {
                 log::error!("Failed to load store data: {}, using default", e);
+                load_errors.push(e);
                 StoreData::default()
             });
 
-            let server_url = store.server_url.clone();
-            let user_name = store.user_name.clone();
-
-            let memo_meta =
-                memo::load_memo_meta_data(app.handle(), &server_url.read(), &user_name.read())
-                    .unwrap_or_else(|e| {
-                        log::error!("Failed to load memo data: {}, using default", e);
-                        DashMap::new()
-                    });
-            let cache = CacheData {
-                all_memo_meta: memo_meta,
-                ..Default::default()
-            };
-
-            let cache = Arc::new(cache);
-            let app_state = AppState {
-                store,
-                cache: cache.clone(),
-            };
+            let server_url = store.server_url.read().clone();
+            let user_name = store.user_name.read().clone();
 
+            let app_state = AppState::new(store, kv_store, memo_store);
+            app_state.load_errors.write().extend(load_errors);
+            if !server_url.is_empty() && !user_name.is_empty() {
+                let (cache, outbox) =
+                    profile::load_account(app.handle(), &app_state, &server_url, &user_name);
+                *app_state.cache.write() = Arc::new(cache);
+                *app_state.outbox.write() = Arc::new(outbox);
+            }
+
+            memo::warm_up_memo_cache(&app_state);
             app.manage(app_state);
 
-            memo::warm_up_memo_cache(app.handle(), app.state());
+            persist_memo_cache(app.handle().clone());
 
-            persist_memo_cache(
-                app.path()
-                    .app_cache_dir()
-                    .expect("Failed to get app cache dir"),
-                cache.clone(),
-                server_url,
-                user_name,
-            );
+            app.manage(sync::SyncState::default());
+            app.manage(sync::auth::AuthState::default());
+            sync::auth::start_auth_watch(app.handle().clone());
+            sync::start_memo_sync(app.handle().clone());
+            sync::outbox::start_outbox_replay(app.handle().clone());
 
             Ok(())
         })
@@ -215,11 +223,49 @@ pub fn run() {
             store::store_data,
             store::get_data,
             store::remove_data,
+            store::get_load_errors,
             store::memo::store_memo,
             store::memo::get_memo,
             store::memo::delete_memo,
             store::memo::get_memo_list,
+            store::memo::get_memo_page,
+            store::memo::get_filtered_memo_page,
+            store::memo::set_memo_cache_limits,
+            store::search::search_memos,
+            store::search::search_tags,
+            store::profile::list_profiles,
+            store::profile::switch_profile,
+            store::profile::remove_profile,
+            store::outbox::outbox_create_memo,
+            store::outbox::outbox_update_memo,
+            store::outbox::outbox_set_memo_state,
+            store::outbox::outbox_pin_memo,
+            store::outbox::outbox_delete_memo,
+            store::outbox::get_outbox,
+            store::outbox::clear_failed_outbox,
+            store::outbox::get_memo_conflicts,
+            store::outbox::resolve_memo_conflict,
+            sync::detect_server_version,
+            sync::sync_memos,
+            sync::auth::get_auth_status,
+            sync::outbox::flush_outbox,
+            sync::shortcut::list_shortcuts,
+            sync::shortcut::create_shortcut,
+            sync::shortcut::update_shortcut,
+            sync::shortcut::delete_shortcut,
+            sync::shortcut::get_shortcut_memo_page,
+            markdown::parse_markdown,
+            markdown::restore_markdown_nodes,
         ])
-        .run(tauri::generate_context!())
-        .expect("error while running tauri application");
+        .build(tauri::generate_context!())
+        .expect("error while building tauri application")
+        .run(|app, event| match event {
+            // Mobile apps can be killed at any time once in the background.
+            tauri::RunEvent::Exit
+            | tauri::RunEvent::WindowEvent {
+                event: tauri::WindowEvent::Focused(false),
+                ..
+            } => memo::flush_active_cache(&app.state::<AppState>()),
+            _ => {}
+        });
 }
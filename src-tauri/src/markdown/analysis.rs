@@ -0,0 +1,121 @@
+use crate::{
+    api::models::{V1MemoProperty, V1Node, V1NodeType},
+    markdown::parser::parse,
+};
+
+/// What the server derives from memo content when it saves a memo.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ContentAnalysis {
+    /// Tags in order of appearance, nested ones like `a/b` as they are.
+    pub tags: Vec<String>,
+    pub property: V1MemoProperty,
+}
+
+/// Analyses content the way the server does on save, so memos changed
+/// offline have the same tags and property it would give them.
+pub fn analyse(content: &str) -> ContentAnalysis {
+    let mut analysis = ContentAnalysis {
+        tags: Vec::new(),
+        property: V1MemoProperty {
+            has_link: Some(false),
+            has_task_list: Some(false),
+            has_code: Some(false),
+            has_incomplete_tasks: Some(false),
+        },
+    };
+    visit(&parse(content), &mut analysis);
+    analysis
+}
+
+fn visit(nodes: &[V1Node], analysis: &mut ContentAnalysis) {
+    for node in nodes {
+        let property = &mut analysis.property;
+        match node.r#type {
+            Some(V1NodeType::Tag) => {
+                if let Some(tag) = node.tag_node.as_ref().and_then(|x| x.content.as_ref())
+                    && !analysis.tags.contains(tag)
+                {
+                    analysis.tags.push(tag.clone());
+                }
+            }
+            Some(V1NodeType::Link | V1NodeType::AutoLink) => property.has_link = Some(true),
+            Some(V1NodeType::TaskListItem) => {
+                property.has_task_list = Some(true);
+                if node
+                    .task_list_item_node
+                    .as_ref()
+                    .is_some_and(|x| x.complete != Some(true))
+                {
+                    property.has_incomplete_tasks = Some(true);
+                }
+            }
+            Some(V1NodeType::Code | V1NodeType::CodeBlock) => property.has_code = Some(true),
+            _ => {}
+        }
+        for children in children(node) {
+            visit(children, analysis);
+        }
+    }
+}
+
+/// Child node lists of block and inline containers.
+fn children(node: &V1Node) -> Vec<&[V1Node]> {
+    let mut lists = vec![
+        node.paragraph_node
+            .as_ref()
+            .and_then(|x| x.children.as_deref()),
+        node.heading_node
+            .as_ref()
+            .and_then(|x| x.children.as_deref()),
+        node.blockquote_node
+            .as_ref()
+            .and_then(|x| x.children.as_deref()),
+        node.list_node.as_ref().and_then(|x| x.children.as_deref()),
+        node.ordered_list_item_node
+            .as_ref()
+            .and_then(|x| x.children.as_deref()),
+        node.unordered_list_item_node
+            .as_ref()
+            .and_then(|x| x.children.as_deref()),
+        node.task_list_item_node
+            .as_ref()
+            .and_then(|x| x.children.as_deref()),
+        node.bold_node.as_ref().and_then(|x| x.children.as_deref()),
+        node.italic_node
+            .as_ref()
+            .and_then(|x| x.children.as_deref()),
+        node.link_node.as_ref().and_then(|x| x.content.as_deref()),
+    ];
+    if let Some(table) = &node.table_node {
+        lists.push(table.header.as_deref());
+        lists.extend(table.rows.iter().flatten().map(|x| x.cells.as_deref()));
+    }
+    lists.into_iter().flatten().collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_analyse() {
+        let analysis = analyse(
+            "#work/meeting notes #idea\n\n- [x] done #idea\n- [ ] **todo #urgent**\n\n```\n#not-a-tag\n```",
+        );
+        assert_eq!(analysis.tags, ["work/meeting", "idea", "urgent"]);
+        assert_eq!(
+            analysis.property,
+            V1MemoProperty {
+                has_link: Some(false),
+                has_task_list: Some(true),
+                has_code: Some(true),
+                has_incomplete_tasks: Some(true),
+            }
+        );
+
+        let property = analyse("see [docs](https://x.y) and `code`").property;
+        assert_eq!(property.has_link, Some(true));
+        assert_eq!(property.has_code, Some(true));
+        assert_eq!(analyse("").tags, Vec::<String>::new());
+    }
+}
@@ -0,0 +1,34 @@
+use crate::api::models::{
+    V1ParseMarkdownRequest, V1ParseMarkdownResponse, V1RestoreMarkdownNodesRequest,
+    V1RestoreMarkdownNodesResponse,
+};
+
+pub mod analysis;
+pub mod parser;
+pub mod restore;
+
+/// Like the server's `ParseMarkdown`, without a server.
+#[tauri::command]
+pub async fn parse_markdown(
+    request: V1ParseMarkdownRequest,
+) -> Result<V1ParseMarkdownResponse, String> {
+    log::trace!("[parse_markdown] request: {:?}", request);
+
+    let markdown = request.markdown.unwrap_or_default();
+    Ok(V1ParseMarkdownResponse {
+        nodes: Some(parser::parse(&markdown)),
+    })
+}
+
+/// Like the server's `RestoreMarkdownNodes`, without a server.
+#[tauri::command]
+pub async fn restore_markdown_nodes(
+    request: V1RestoreMarkdownNodesRequest,
+) -> Result<V1RestoreMarkdownNodesResponse, String> {
+    log::trace!("[restore_markdown_nodes] request: {:?}", request);
+
+    let nodes = request.nodes.unwrap_or_default();
+    Ok(V1RestoreMarkdownNodesResponse {
+        markdown: Some(restore::restore(&nodes)),
+    })
+}
@@ -0,0 +1,858 @@
+use crate::api::models::{
+    ListNodeKind, TableNodeRow, V1AutoLinkNode, V1BlockquoteNode, V1BoldItalicNode, V1BoldNode,
+    V1CodeBlockNode, V1CodeNode, V1EmbeddedContentNode, V1EscapingCharacterNode, V1HeadingNode,
+    V1HighlightNode, V1HorizontalRuleNode, V1HtmlElementNode, V1ImageNode, V1ItalicNode,
+    V1LinkNode, V1ListNode, V1MathBlockNode, V1MathNode, V1Node, V1NodeType, V1OrderedListItemNode,
+    V1ParagraphNode, V1ReferencedContentNode, V1SpoilerNode, V1StrikethroughNode, V1SubscriptNode,
+    V1SuperscriptNode, V1TableNode, V1TagNode, V1TaskListItemNode, V1TextNode,
+    V1UnorderedListItemNode,
+};
+
+/// Characters the server tokenizes on their own. Inline syntax only starts
+/// at a token, so `xhttps://a.b` is no link.
+const SYMBOLS: &str = "_*#`[]()!?~-+.<>$=|:^\\/ \n";
+
+/// Parses memo content into the nodes the server's `ParseMarkdown` returns.
+/// Blocks are separated by `LINE_BREAK` nodes and list items are grouped
+/// into `LIST` nodes.
+pub fn parse(content: &str) -> Vec<V1Node> {
+    merge_lists(parse_blocks(content))
+}
+
+fn node(r#type: V1NodeType) -> V1Node {
+    V1Node {
+        r#type: Some(r#type),
+        ..Default::default()
+    }
+}
+
+fn text(content: &str) -> V1Node {
+    V1Node {
+        text_node: Some(Box::new(V1TextNode {
+            content: Some(content.to_string()),
+        })),
+        ..node(V1NodeType::Text)
+    }
+}
+
+fn line_break() -> V1Node {
+    V1Node {
+        line_break_node: Some(serde_json::Value::Object(Default::default())),
+        ..node(V1NodeType::LineBreak)
+    }
+}
+
+fn paragraph(line: &str) -> V1Node {
+    V1Node {
+        paragraph_node: Some(Box::new(V1ParagraphNode {
+            children: Some(parse_inline(line)),
+        })),
+        ..node(V1NodeType::Paragraph)
+    }
+}
+
+/// Up to the first newline.
+fn first_line(text: &str) -> &str {
+    text.split('\n').next().unwrap_or_default()
+}
+
+/// A block parser, the node and how many bytes it takes.
+type Block = Option<(V1Node, usize)>;
+
+fn parse_blocks(text: &str) -> Vec<V1Node> {
+    let mut nodes = Vec::new();
+    let mut rest = text;
+    while !rest.is_empty() {
+        let block = code_block(rest)
+            .or_else(|| table(rest))
+            .or_else(|| horizontal_rule(rest))
+            .or_else(|| heading(rest))
+            .or_else(|| blockquote(rest))
+            .or_else(|| task_list_item(rest))
+            .or_else(|| unordered_list_item(rest))
+            .or_else(|| ordered_list_item(rest))
+            .or_else(|| math_block(rest))
+            .or_else(|| embedded_content(rest));
+        let (node, size) = block.unwrap_or_else(|| match first_line(rest) {
+            "" => (line_break(), 1),
+            line => (paragraph(line), line.len()),
+        });
+        nodes.push(node);
+        rest = &rest[size..];
+    }
+    nodes
+}
+
+/// Lines between an opening line starting with `open` and a line that is
+/// just `close`. The opening line's rest and the lines in between.
+fn fenced<'a>(text: &'a str, open: &str, close: &str) -> Option<(&'a str, String, usize)> {
+    let head = first_line(text);
+    let info = head.strip_prefix(open)?;
+    let mut size = head.len();
+    let mut lines = Vec::new();
+    for line in text[head.len()..].strip_prefix('\n')?.split('\n') {
+        size += 1 + line.len();
+        if line == close {
+            return Some((info, lines.join("\n"), size));
+        }
+        lines.push(line);
+    }
+    None
+}
+
+fn code_block(text: &str) -> Block {
+    let (language, content, size) = fenced(text, "```", "```")?;
+    if language.contains(['`', ' ']) {
+        return None;
+    }
+    let node = V1Node {
+        code_block_node: Some(Box::new(V1CodeBlockNode {
+            language: Some(language.to_string()),
+            content: Some(content),
+        })),
+        ..node(V1NodeType::CodeBlock)
+    };
+    Some((node, size))
+}
+
+fn math_block(text: &str) -> Block {
+    let (rest, content, size) = fenced(text, "$$", "$$")?;
+    if !rest.is_empty() {
+        return None;
+    }
+    let node = V1Node {
+        math_block_node: Some(Box::new(V1MathBlockNode {
+            content: Some(content),
+        })),
+        ..node(V1NodeType::MathBlock)
+    };
+    Some((node, size))
+}
+
+/// Trimmed cells of a `| a | b |` row.
+fn table_cells(line: &str) -> Option<Vec<&str>> {
+    let inner = line.strip_prefix('|')?.strip_suffix('|')?;
+    Some(inner.split('|').map(str::trim).collect())
+}
+
+fn is_delimiter(cell: &str) -> bool {
+    let dashes = cell.strip_prefix(':').unwrap_or(cell);
+    let dashes = dashes.strip_suffix(':').unwrap_or(dashes);
+    !dashes.is_empty() && dashes.chars().all(|c| c == '-')
+}
+
+fn table_cell(cell: &str) -> V1Node {
+    paragraph(cell)
+}
+
+fn table(text: &str) -> Block {
+    let mut lines = text.split('\n');
+    let (header_line, delimiter_line) = (lines.next()?, lines.next()?);
+    let header = table_cells(header_line)?;
+    let delimiter = table_cells(delimiter_line)?;
+    if delimiter.len() != header.len() || !delimiter.iter().all(|x| is_delimiter(x)) {
+        return None;
+    }
+
+    let mut size = header_line.len() + 1 + delimiter_line.len();
+    let mut rows = Vec::new();
+    for line in lines {
+        match table_cells(line) {
+            Some(cells) if cells.len() == header.len() => {
+                size += 1 + line.len();
+                rows.push(TableNodeRow {
+                    cells: Some(cells.into_iter().map(table_cell).collect()),
+                });
+            }
+            _ => break,
+        }
+    }
+
+    let node = V1Node {
+        table_node: Some(Box::new(V1TableNode {
+            header: Some(header.into_iter().map(table_cell).collect()),
+            delimiter: Some(delimiter.into_iter().map(str::to_string).collect()),
+            rows: Some(rows),
+        })),
+        ..node(V1NodeType::Table)
+    };
+    Some((node, size))
+}
+
+fn horizontal_rule(text: &str) -> Block {
+    let line = first_line(text);
+    let symbol = match line {
+        "---" | "***" | "___" => &line[..1],
+        _ => return None,
+    };
+    let node = V1Node {
+        horizontal_rule_node: Some(Box::new(V1HorizontalRuleNode {
+            symbol: Some(symbol.to_string()),
+        })),
+        ..node(V1NodeType::HorizontalRule)
+    };
+    Some((node, line.len()))
+}
+
+fn heading(text: &str) -> Block {
+    let line = first_line(text);
+    let level = line.bytes().take_while(|x| *x == b'#').count();
+    let content = line[level..].strip_prefix(' ')?;
+    if !(1..=6).contains(&level) || content.is_empty() {
+        return None;
+    }
+    let node = V1Node {
+        heading_node: Some(Box::new(V1HeadingNode {
+            level: Some(level as i32),
+            children: Some(parse_inline(content)),
+        })),
+        ..node(V1NodeType::Heading)
+    };
+    Some((node, line.len()))
+}
+
+/// Deeper `> ` prefixes stay text, so a long run of them can't overflow
+/// the stack.
+const MAX_QUOTE_DEPTH: usize = 32;
+
+/// Each `> ` line is a child, nested quotes included.
+fn blockquote(text: &str) -> Block {
+    nested_blockquote(text, 1)
+}
+
+/// `blockquote` nested `depth` levels deep, counting from 1.
+fn nested_blockquote(text: &str, depth: usize) -> Block {
+    let mut children = Vec::new();
+    let mut size = 0;
+    for line in text.split('\n') {
+        let Some(content) = line.strip_prefix("> ").filter(|x| !x.is_empty()) else {
+            break;
+        };
+        let nested = if depth < MAX_QUOTE_DEPTH {
+            nested_blockquote(content, depth + 1)
+        } else {
+            None
+        };
+        let child = match nested {
+            Some((node, _)) => node,
+            None => paragraph(content),
+        };
+        children.push(child);
+        size += line.len() + 1;
+    }
+    if children.is_empty() {
+        return None;
+    }
+    let node = V1Node {
+        blockquote_node: Some(Box::new(V1BlockquoteNode {
+            children: Some(children),
+        })),
+        ..node(V1NodeType::Blockquote)
+    };
+    // The newline after the last line is a line break of its own.
+    Some((node, size - 1))
+}
+
+/// Leading spaces of a list item and the rest of its line.
+fn indented(line: &str) -> (usize, &str) {
+    let content = line.trim_start_matches(' ');
+    (line.len() - content.len(), content)
+}
+
+/// `-`, `*` or `+` and a space.
+fn bullet(line: &str) -> Option<(&str, &str)> {
+    let symbol = line.get(..1).filter(|x| ["-", "*", "+"].contains(x))?;
+    Some((symbol, line[1..].strip_prefix(' ')?))
+}
+
+fn task_list_item(text: &str) -> Block {
+    let line = first_line(text);
+    let (indent, rest) = indented(line);
+    let (symbol, rest) = bullet(rest)?;
+    let complete = match rest.get(..4)? {
+        "[ ] " => false,
+        "[x] " | "[X] " => true,
+        _ => return None,
+    };
+    let content = &rest[4..];
+    if content.is_empty() {
+        return None;
+    }
+    let node = V1Node {
+        task_list_item_node: Some(Box::new(V1TaskListItemNode {
+            symbol: Some(symbol.to_string()),
+            indent: Some(indent as i32),
+            complete: Some(complete),
+            children: Some(parse_inline(content)),
+        })),
+        ..node(V1NodeType::TaskListItem)
+    };
+    Some((node, line.len()))
+}
+
+fn unordered_list_item(text: &str) -> Block {
+    let line = first_line(text);
+    let (indent, rest) = indented(line);
+    let (symbol, content) = bullet(rest)?;
+    if content.is_empty() {
+        return None;
+    }
+    let node = V1Node {
+        unordered_list_item_node: Some(Box::new(V1UnorderedListItemNode {
+            symbol: Some(symbol.to_string()),
+            indent: Some(indent as i32),
+            children: Some(parse_inline(content)),
+        })),
+        ..node(V1NodeType::UnorderedListItem)
+    };
+    Some((node, line.len()))
+}
+
+fn ordered_list_item(text: &str) -> Block {
+    let line = first_line(text);
+    let (indent, rest) = indented(line);
+    let digits = rest.bytes().take_while(u8::is_ascii_digit).count();
+    let content = rest[digits..].strip_prefix(". ")?;
+    if digits == 0 || content.is_empty() {
+        return None;
+    }
+    let node = V1Node {
+        ordered_list_item_node: Some(Box::new(V1OrderedListItemNode {
+            number: Some(rest[..digits].to_string()),
+            indent: Some(indent as i32),
+            children: Some(parse_inline(content)),
+        })),
+        ..node(V1NodeType::OrderedListItem)
+    };
+    Some((node, line.len()))
+}
+
+/// `name?params` of embedded and referenced content.
+fn resource(inner: &str) -> Option<(String, String)> {
+    let (name, params) = inner.split_once('?').unwrap_or((inner, ""));
+    if name.is_empty() || name.contains([' ', '[', ']']) {
+        return None;
+    }
+    Some((name.to_string(), params.to_string()))
+}
+
+fn embedded_content(text: &str) -> Block {
+    let line = first_line(text);
+    let inner = line.strip_prefix("![[")?.strip_suffix("]]")?;
+    let (name, params) = resource(inner)?;
+    let node = V1Node {
+        embedded_content_node: Some(Box::new(V1EmbeddedContentNode {
+            resource_name: Some(name),
+            params: Some(params),
+        })),
+        ..node(V1NodeType::EmbeddedContent)
+    };
+    Some((node, line.len()))
+}
+
+/// The list a list item goes into and its indent.
+fn list_item(node: &V1Node) -> Option<(ListNodeKind, i32)> {
+    match node.r#type? {
+        V1NodeType::OrderedListItem => Some((
+            ListNodeKind::Ordered,
+            node.ordered_list_item_node.as_ref()?.indent?,
+        )),
+        V1NodeType::UnorderedListItem => Some((
+            ListNodeKind::Unordered,
+            node.unordered_list_item_node.as_ref()?.indent?,
+        )),
+        V1NodeType::TaskListItem => Some((
+            ListNodeKind::Description,
+            node.task_list_item_node.as_ref()?.indent?,
+        )),
+        _ => None,
+    }
+}
+
+fn list_children(list: &mut V1Node) -> &mut Vec<V1Node> {
+    list.list_node
+        .as_mut()
+        .and_then(|x| x.children.as_mut())
+        .expect("list node has children")
+}
+
+fn close_list(stack: &mut Vec<(ListNodeKind, i32, V1Node)>, result: &mut Vec<V1Node>) {
+    if let Some((_, _, list)) = stack.pop() {
+        match stack.last_mut() {
+            Some((_, _, parent)) => list_children(parent).push(list),
+            None => result.push(list),
+        }
+    }
+}
+
+/// Groups runs of list items, and the line breaks between them, into
+/// lists. Deeper indented items go into a list nested in the current one.
+fn merge_lists(nodes: Vec<V1Node>) -> Vec<V1Node> {
+    let mut result = Vec::new();
+    let mut stack: Vec<(ListNodeKind, i32, V1Node)> = Vec::new();
+    let mut nodes = nodes.into_iter().peekable();
+    while let Some(node) = nodes.next() {
+        if let Some((kind, indent)) = list_item(&node) {
+            while let Some((top_kind, top_indent, _)) = stack.last() {
+                if indent < *top_indent || (indent == *top_indent && kind != *top_kind) {
+                    close_list(&mut stack, &mut result);
+                } else {
+                    break;
+                }
+            }
+            if stack
+                .last()
+                .is_none_or(|(_, top_indent, _)| indent > *top_indent)
+            {
+                let list = V1Node {
+                    list_node: Some(Box::new(V1ListNode {
+                        kind: Some(kind),
+                        indent: Some(indent),
+                        children: Some(Vec::new()),
+                    })),
+                    ..self::node(V1NodeType::List)
+                };
+                stack.push((kind, indent, list));
+            }
+            if let Some((_, _, list)) = stack.last_mut() {
+                list_children(list).push(node);
+            }
+        } else if node.r#type == Some(V1NodeType::LineBreak)
+            && nodes.peek().is_some_and(|x| list_item(x).is_some())
+            && let Some((_, _, list)) = stack.last_mut()
+        {
+            list_children(list).push(node);
+        } else {
+            while !stack.is_empty() {
+                close_list(&mut stack, &mut result);
+            }
+            result.push(node);
+        }
+    }
+    while !stack.is_empty() {
+        close_list(&mut stack, &mut result);
+    }
+    result
+}
+
+fn is_symbol(c: char) -> bool {
+    SYMBOLS.contains(c)
+}
+
+/// Whether a token of the server's tokenizer starts at byte `i`: a symbol,
+/// right after one, or where digits and other text meet.
+fn is_token_start(text: &str, i: usize) -> bool {
+    let Some(prev) = text[..i].chars().next_back() else {
+        return true;
+    };
+    let c = text[i..].chars().next().unwrap_or(' ');
+    is_symbol(prev) || is_symbol(c) || prev.is_ascii_digit() != c.is_ascii_digit()
+}
+
+/// Parses one line of inline syntax, adjacent text merged.
+pub fn parse_inline(line: &str) -> Vec<V1Node> {
+    let mut nodes: Vec<V1Node> = Vec::new();
+    let mut i = 0;
+    while i < line.len() {
+        let rest = &line[i..];
+        let parsed = if is_token_start(line, i) {
+            inline_node(rest)
+        } else {
+            None
+        };
+        let (node, size) = parsed.unwrap_or_else(|| {
+            let len = rest.chars().next().map_or(1, char::len_utf8);
+            (text(&rest[..len]), len)
+        });
+        i += size;
+
+        let last = nodes.last_mut().and_then(|x| x.text_node.as_mut());
+        match (last, node.text_node) {
+            (Some(last), Some(next)) => {
+                let content = last.content.get_or_insert_default();
+                content.push_str(next.content.as_deref().unwrap_or_default());
+            }
+            (_, text_node) => nodes.push(V1Node { text_node, ..node }),
+        }
+    }
+    nodes
+}
+
+/// Inline syntax in the server's order of precedence.
+fn inline_node(text: &str) -> Block {
+    escaping_character(text)
+        .or_else(|| html_element(text))
+        .or_else(|| bold_italic(text))
+        .or_else(|| image(text))
+        .or_else(|| link(text))
+        .or_else(|| auto_link(text))
+        .or_else(|| bold(text))
+        .or_else(|| italic(text))
+        .or_else(|| {
+            let (content, size) = delimited(text, "||", "||")?;
+            let node = V1Node {
+                spoiler_node: Some(Box::new(V1SpoilerNode {
+                    content: Some(content.to_string()),
+                })),
+                ..node(V1NodeType::Spoiler)
+            };
+            Some((node, size))
+        })
+        .or_else(|| {
+            let (content, size) = delimited(text, "==", "==")?;
+            let node = V1Node {
+                highlight_node: Some(Box::new(V1HighlightNode {
+                    content: Some(content.to_string()),
+                })),
+                ..node(V1NodeType::Highlight)
+            };
+            Some((node, size))
+        })
+        .or_else(|| {
+            let (content, size) = delimited(text, "`", "`")?;
+            let node = V1Node {
+                code_node: Some(Box::new(V1CodeNode {
+                    content: Some(content.to_string()),
+                })),
+                ..node(V1NodeType::Code)
+            };
+            Some((node, size))
+        })
+        .or_else(|| {
+            let (content, size) = delimited(text, "~", "~")?;
+            let node = V1Node {
+                subscript_node: Some(Box::new(V1SubscriptNode {
+                    content: Some(content.to_string()),
+                })),
+                ..node(V1NodeType::Subscript)
+            };
+            Some((node, size))
+        })
+        .or_else(|| {
+            let (content, size) = delimited(text, "^", "^")?;
+            let node = V1Node {
+                superscript_node: Some(Box::new(V1SuperscriptNode {
+                    content: Some(content.to_string()),
+                })),
+                ..node(V1NodeType::Superscript)
+            };
+            Some((node, size))
+        })
+        .or_else(|| {
+            let (content, size) = delimited(text, "$", "$")?;
+            let node = V1Node {
+                math_node: Some(Box::new(V1MathNode {
+                    content: Some(content.to_string()),
+                })),
+                ..node(V1NodeType::Math)
+            };
+            Some((node, size))
+        })
+        .or_else(|| referenced_content(text))
+        .or_else(|| tag(text))
+        .or_else(|| {
+            let (content, size) = delimited(text, "~~", "~~")?;
+            let node = V1Node {
+                strikethrough_node: Some(Box::new(V1StrikethroughNode {
+                    content: Some(content.to_string()),
+                })),
+                ..node(V1NodeType::Strikethrough)
+            };
+            Some((node, size))
+        })
+}
+
+/// Non-empty content between `open` and the next `close`, and the size of
+/// it all.
+fn delimited<'a>(text: &'a str, open: &str, close: &str) -> Option<(&'a str, usize)> {
+    let rest = text.strip_prefix(open)?;
+    let end = rest.find(close)?;
+    if end == 0 {
+        return None;
+    }
+    Some((&rest[..end], open.len() + end + close.len()))
+}
+
+fn escaping_character(text: &str) -> Block {
+    let symbol = text.strip_prefix('\\')?.chars().next()?;
+    if !is_symbol(symbol) || symbol == ' ' || symbol == '\n' {
+        return None;
+    }
+    let node = V1Node {
+        escaping_character_node: Some(Box::new(V1EscapingCharacterNode {
+            symbol: Some(symbol.to_string()),
+        })),
+        ..node(V1NodeType::EscapingCharacter)
+    };
+    Some((node, 1 + symbol.len_utf8()))
+}
+
+/// Only line breaks, `<br>`, `<br/>` or `<br />`.
+fn html_element(text: &str) -> Block {
+    let size = ["<br />", "<br/>", "<br>"]
+        .iter()
+        .find(|x| text.starts_with(**x))?
+        .len();
+    let node = V1Node {
+        html_element_node: Some(Box::new(V1HtmlElementNode {
+            tag_name: Some("br".to_string()),
+            attributes: None,
+        })),
+        ..node(V1NodeType::HtmlElement)
+    };
+    Some((node, size))
+}
+
+fn bold_italic(text: &str) -> Block {
+    let symbol = text.get(..1).filter(|x| *x == "*" || *x == "_")?;
+    let marker = symbol.repeat(3);
+    let (content, size) = delimited(text, &marker, &marker)?;
+    if content.contains(symbol) {
+        return None;
+    }
+    let node = V1Node {
+        bold_italic_node: Some(Box::new(V1BoldItalicNode {
+            symbol: Some(symbol.to_string()),
+            content: Some(content.to_string()),
+        })),
+        ..node(V1NodeType::BoldItalic)
+    };
+    Some((node, size))
+}
+
+/// `[text](url)`, the text and url. Urls have no spaces.
+fn link_parts(text: &str) -> Option<(&str, &str, usize)> {
+    let rest = text.strip_prefix('[')?;
+    let (content, rest) = rest.split_once("](")?;
+    let (url, _) = rest.split_once(')')?;
+    if content.contains(['[', ']']) || url.is_empty() || url.contains(' ') {
+        return None;
+    }
+    Some((content, url, content.len() + url.len() + 4))
+}
+
+fn image(text: &str) -> Block {
+    let (alt, url, size) = link_parts(text.strip_prefix('!')?)?;
+    let node = V1Node {
+        image_node: Some(Box::new(V1ImageNode {
+            alt_text: Some(alt.to_string()),
+            url: Some(url.to_string()),
+        })),
+        ..node(V1NodeType::Image)
+    };
+    Some((node, size + 1))
+}
+
+fn link(text: &str) -> Block {
+    let (content, url, size) = link_parts(text)?;
+    if content.is_empty() {
+        return None;
+    }
+    let node = V1Node {
+        link_node: Some(Box::new(V1LinkNode {
+            content: Some(parse_inline(content)),
+            url: Some(url.to_string()),
+        })),
+        ..node(V1NodeType::Link)
+    };
+    Some((node, size))
+}
+
+/// Urls with a scheme and a host, like the server accepts.
+fn is_url(text: &str) -> bool {
+    url::Url::parse(text).is_ok_and(|x| x.host_str().is_some_and(|x| !x.is_empty()))
+}
+
+/// `<https://x.y>`, or a bare url up to the next space.
+fn auto_link(text: &str) -> Block {
+    let (url, size, is_raw_text) = match text.strip_prefix('<') {
+        Some(rest) => {
+            let (url, _) = rest.split_once('>')?;
+            (url, url.len() + 2, false)
+        }
+        None => {
+            let url = text.split(' ').next().unwrap_or_default();
+            (url, url.len(), true)
+        }
+    };
+    if url.contains(' ') || !is_url(url) {
+        return None;
+    }
+    let node = V1Node {
+        auto_link_node: Some(Box::new(V1AutoLinkNode {
+            url: Some(url.to_string()),
+            is_raw_text: Some(is_raw_text),
+        })),
+        ..node(V1NodeType::AutoLink)
+    };
+    Some((node, size))
+}
+
+fn bold(text: &str) -> Block {
+    let symbol = text.get(..1).filter(|x| *x == "*" || *x == "_")?;
+    let marker = symbol.repeat(2);
+    let (content, size) = delimited(text, &marker, &marker)?;
+    let node = V1Node {
+        bold_node: Some(Box::new(V1BoldNode {
+            symbol: Some(symbol.to_string()),
+            children: Some(parse_inline(content)),
+        })),
+        ..node(V1NodeType::Bold)
+    };
+    Some((node, size))
+}
+
+fn italic(text: &str) -> Block {
+    let symbol = text.get(..1).filter(|x| *x == "*" || *x == "_")?;
+    let (content, size) = delimited(text, symbol, symbol)?;
+    let node = V1Node {
+        italic_node: Some(Box::new(V1ItalicNode {
+            symbol: Some(symbol.to_string()),
+            children: Some(parse_inline(content)),
+        })),
+        ..node(V1NodeType::Italic)
+    };
+    Some((node, size))
+}
+
+fn referenced_content(text: &str) -> Block {
+    let (inner, size) = delimited(text, "[[", "]]")?;
+    let (name, params) = resource(inner)?;
+    let node = V1Node {
+        referenced_content_node: Some(Box::new(V1ReferencedContentNode {
+            resource_name: Some(name),
+            params: Some(params),
+        })),
+        ..node(V1NodeType::ReferencedContent)
+    };
+    Some((node, size))
+}
+
+/// `#` and everything up to a space or the next `#`, so `#a/b` is one tag.
+fn tag(text: &str) -> Block {
+    let rest = text.strip_prefix('#')?;
+    let content = rest.split([' ', '#']).next().unwrap_or_default();
+    if content.is_empty() {
+        return None;
+    }
+    let node = V1Node {
+        tag_node: Some(Box::new(V1TagNode {
+            content: Some(content.to_string()),
+        })),
+        ..node(V1NodeType::Tag)
+    };
+    Some((node, content.len() + 1))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn types(nodes: &[V1Node]) -> Vec<V1NodeType> {
+        nodes.iter().filter_map(|x| x.r#type).collect()
+    }
+
+    #[test]
+    fn test_parse() {
+        use V1NodeType::*;
+
+        let nodes = parse(
+            "# Title\n\n```rust\nfn a() {}\n```\n> quote\n---\n$$\nx^2\n$$\n![[resources/1]]",
+        );
+        assert_eq!(
+            types(&nodes),
+            [
+                Heading,
+                LineBreak,
+                LineBreak,
+                CodeBlock,
+                LineBreak,
+                Blockquote,
+                LineBreak,
+                HorizontalRule,
+                LineBreak,
+                MathBlock,
+                LineBreak,
+                EmbeddedContent
+            ]
+        );
+        let code = nodes[3].code_block_node.as_ref().unwrap();
+        assert_eq!(code.language.as_deref(), Some("rust"));
+        assert_eq!(code.content.as_deref(), Some("fn a() {}"));
+
+        let nodes = parse("- [ ] a\n  1. b\n- [x] c\n\ntext");
+        assert_eq!(types(&nodes), [List, LineBreak, LineBreak, Paragraph]);
+        let list = nodes[0].list_node.as_ref().unwrap();
+        assert_eq!(list.kind, Some(ListNodeKind::Description));
+        let children = list.children.as_ref().unwrap();
+        assert_eq!(
+            types(children),
+            [TaskListItem, LineBreak, List, TaskListItem]
+        );
+        let nested = children[2].list_node.as_ref().unwrap();
+        assert_eq!(nested.kind, Some(ListNodeKind::Ordered));
+        assert_eq!(nested.indent, Some(2));
+
+        let nodes = parse("| a | b |\n| --- | :-: |\n| 1 | 2 |\nx");
+        let table = nodes[0].table_node.as_ref().unwrap();
+        assert_eq!(table.delimiter.as_ref().unwrap(), &["---", ":-:"]);
+        assert_eq!(table.rows.as_ref().unwrap().len(), 1);
+        assert_eq!(types(&nodes), [Table, LineBreak, Paragraph]);
+    }
+
+    #[test]
+    fn test_blockquote_depth() {
+        use V1NodeType::*;
+
+        let nodes = parse(&format!("{}x", "> ".repeat(10_000)));
+        let mut node = &nodes[0];
+        let mut depth = 0;
+        while let Some(quote) = &node.blockquote_node {
+            node = &quote.children.as_ref().unwrap()[0];
+            depth += 1;
+        }
+        assert_eq!(depth, MAX_QUOTE_DEPTH);
+        assert_eq!(types(std::slice::from_ref(node)), [Paragraph]);
+    }
+
+    #[test]
+    fn test_parse_inline() {
+        use V1NodeType::*;
+
+        let nodes = parse_inline(
+            "a#b/c **bold _it_** `code` ==hi== ||sp|| [[memos/1]] ~~s~~ https://x.y/z [l](u) \\*",
+        );
+        assert_eq!(
+            types(&nodes),
+            [
+                Text,
+                Tag,
+                Text,
+                Bold,
+                Text,
+                Code,
+                Text,
+                Highlight,
+                Text,
+                Spoiler,
+                Text,
+                ReferencedContent,
+                Text,
+                Strikethrough,
+                Text,
+                AutoLink,
+                Text,
+                Link,
+                Text,
+                EscapingCharacter
+            ]
+        );
+        assert_eq!(
+            nodes[1].tag_node.as_ref().unwrap().content.as_deref(),
+            Some("b/c")
+        );
+        let bold = nodes[3].bold_node.as_ref().unwrap();
+        assert_eq!(types(bold.children.as_ref().unwrap()), [Text, Italic]);
+        assert_eq!(types(&parse_inline("éhttps://x.y ## #")), [Text]);
+    }
+}
@@ -0,0 +1,283 @@
+use crate::api::models::{V1Node, V1NodeType};
+
+/// Markdown for `nodes`, the reverse of `parse`. HTML elements keep no
+/// spelling, so `<br>` and `<br/>` come back as `<br />`.
+pub fn restore(nodes: &[V1Node]) -> String {
+    let mut markdown = String::new();
+    for node in nodes {
+        restore_node(node, &mut markdown);
+    }
+    markdown
+}
+
+fn restore_children(children: &Option<Vec<V1Node>>, out: &mut String) {
+    for node in children.iter().flatten() {
+        restore_node(node, out);
+    }
+}
+
+fn str_or_empty(value: &Option<String>) -> &str {
+    value.as_deref().unwrap_or_default()
+}
+
+fn indent(value: Option<i32>) -> String {
+    " ".repeat(value.unwrap_or_default().max(0) as usize)
+}
+
+/// `name?params` of embedded and referenced content.
+fn resource(name: &Option<String>, params: &Option<String>) -> String {
+    match str_or_empty(params) {
+        "" => str_or_empty(name).to_string(),
+        params => format!("{}?{}", str_or_empty(name), params),
+    }
+}
+
+/// Table cells as a `| a | b |` row.
+fn table_row(cells: &[String]) -> String {
+    format!("| {} |", cells.join(" | "))
+}
+
+/// Nodes of unknown or mismatched type restore to nothing.
+fn restore_node(node: &V1Node, out: &mut String) {
+    let Some(r#type) = node.r#type else {
+        return;
+    };
+    match r#type {
+        V1NodeType::LineBreak => out.push('\n'),
+        V1NodeType::Paragraph => {
+            if let Some(x) = &node.paragraph_node {
+                restore_children(&x.children, out);
+            }
+        }
+        V1NodeType::CodeBlock => {
+            if let Some(x) = &node.code_block_node {
+                out.push_str(&format!(
+                    "```{}\n{}\n```",
+                    str_or_empty(&x.language),
+                    str_or_empty(&x.content)
+                ));
+            }
+        }
+        V1NodeType::Heading => {
+            if let Some(x) = &node.heading_node {
+                out.push_str(&"#".repeat(x.level.unwrap_or(1).max(1) as usize));
+                out.push(' ');
+                restore_children(&x.children, out);
+            }
+        }
+        V1NodeType::HorizontalRule => {
+            if let Some(x) = &node.horizontal_rule_node {
+                out.push_str(&str_or_empty(&x.symbol).repeat(3));
+            }
+        }
+        V1NodeType::Blockquote => {
+            if let Some(x) = &node.blockquote_node {
+                let lines = x
+                    .children
+                    .iter()
+                    .flatten()
+                    .map(|child| format!("> {}", restore(std::slice::from_ref(child))))
+                    .collect::<Vec<_>>();
+                out.push_str(&lines.join("\n"));
+            }
+        }
+        V1NodeType::List => {
+            if let Some(x) = &node.list_node {
+                restore_children(&x.children, out);
+            }
+        }
+        V1NodeType::OrderedListItem => {
+            if let Some(x) = &node.ordered_list_item_node {
+                out.push_str(&indent(x.indent));
+                out.push_str(&format!("{}. ", str_or_empty(&x.number)));
+                restore_children(&x.children, out);
+            }
+        }
+        V1NodeType::UnorderedListItem => {
+            if let Some(x) = &node.unordered_list_item_node {
+                out.push_str(&indent(x.indent));
+                out.push_str(&format!("{} ", str_or_empty(&x.symbol)));
+                restore_children(&x.children, out);
+            }
+        }
+        V1NodeType::TaskListItem => {
+            if let Some(x) = &node.task_list_item_node {
+                let check = if x.complete.unwrap_or_default() {
+                    'x'
+                } else {
+                    ' '
+                };
+                out.push_str(&indent(x.indent));
+                out.push_str(&format!("{} [{}] ", str_or_empty(&x.symbol), check));
+                restore_children(&x.children, out);
+            }
+        }
+        V1NodeType::MathBlock => {
+            if let Some(x) = &node.math_block_node {
+                out.push_str(&format!("$$\n{}\n$$", str_or_empty(&x.content)));
+            }
+        }
+        V1NodeType::Table => {
+            if let Some(x) = &node.table_node {
+                let cells = |nodes: &Option<Vec<V1Node>>| {
+                    nodes
+                        .iter()
+                        .flatten()
+                        .map(|x| restore(std::slice::from_ref(x)))
+                        .collect::<Vec<_>>()
+                };
+                let mut lines = vec![
+                    table_row(&cells(&x.header)),
+                    table_row(x.delimiter.as_deref().unwrap_or_default()),
+                ];
+                lines.extend(
+                    x.rows
+                        .iter()
+                        .flatten()
+                        .map(|row| table_row(&cells(&row.cells))),
+                );
+                out.push_str(&lines.join("\n"));
+            }
+        }
+        V1NodeType::EmbeddedContent => {
+            if let Some(x) = &node.embedded_content_node {
+                out.push_str(&format!("![[{}]]", resource(&x.resource_name, &x.params)));
+            }
+        }
+        V1NodeType::Text => {
+            if let Some(x) = &node.text_node {
+                out.push_str(str_or_empty(&x.content));
+            }
+        }
+        V1NodeType::Bold => {
+            if let Some(x) = &node.bold_node {
+                let symbol = str_or_empty(&x.symbol).repeat(2);
+                out.push_str(&symbol);
+                restore_children(&x.children, out);
+                out.push_str(&symbol);
+            }
+        }
+        V1NodeType::Italic => {
+            if let Some(x) = &node.italic_node {
+                let symbol = str_or_empty(&x.symbol);
+                out.push_str(symbol);
+                restore_children(&x.children, out);
+                out.push_str(symbol);
+            }
+        }
+        V1NodeType::BoldItalic => {
+            if let Some(x) = &node.bold_italic_node {
+                let symbol = str_or_empty(&x.symbol).repeat(3);
+                out.push_str(&format!("{0}{1}{0}", symbol, str_or_empty(&x.content)));
+            }
+        }
+        V1NodeType::Code => {
+            if let Some(x) = &node.code_node {
+                out.push_str(&format!("`{}`", str_or_empty(&x.content)));
+            }
+        }
+        V1NodeType::Image => {
+            if let Some(x) = &node.image_node {
+                out.push_str(&format!(
+                    "![{}]({})",
+                    str_or_empty(&x.alt_text),
+                    str_or_empty(&x.url)
+                ));
+            }
+        }
+        V1NodeType::Link => {
+            if let Some(x) = &node.link_node {
+                out.push('[');
+                restore_children(&x.content, out);
+                out.push_str(&format!("]({})", str_or_empty(&x.url)));
+            }
+        }
+        V1NodeType::AutoLink => {
+            if let Some(x) = &node.auto_link_node {
+                let url = str_or_empty(&x.url);
+                if x.is_raw_text.unwrap_or_default() {
+                    out.push_str(url);
+                } else {
+                    out.push_str(&format!("<{}>", url));
+                }
+            }
+        }
+        V1NodeType::Tag => {
+            if let Some(x) = &node.tag_node {
+                out.push_str(&format!("#{}", str_or_empty(&x.content)));
+            }
+        }
+        V1NodeType::Strikethrough => {
+            if let Some(x) = &node.strikethrough_node {
+                out.push_str(&format!("~~{}~~", str_or_empty(&x.content)));
+            }
+        }
+        V1NodeType::EscapingCharacter => {
+            if let Some(x) = &node.escaping_character_node {
+                out.push_str(&format!("\\{}", str_or_empty(&x.symbol)));
+            }
+        }
+        V1NodeType::Math => {
+            if let Some(x) = &node.math_node {
+                out.push_str(&format!("${}$", str_or_empty(&x.content)));
+            }
+        }
+        V1NodeType::Highlight => {
+            if let Some(x) = &node.highlight_node {
+                out.push_str(&format!("=={}==", str_or_empty(&x.content)));
+            }
+        }
+        V1NodeType::Subscript => {
+            if let Some(x) = &node.subscript_node {
+                out.push_str(&format!("~{}~", str_or_empty(&x.content)));
+            }
+        }
+        V1NodeType::Superscript => {
+            if let Some(x) = &node.superscript_node {
+                out.push_str(&format!("^{}^", str_or_empty(&x.content)));
+            }
+        }
+        V1NodeType::ReferencedContent => {
+            if let Some(x) = &node.referenced_content_node {
+                out.push_str(&format!("[[{}]]", resource(&x.resource_name, &x.params)));
+            }
+        }
+        V1NodeType::Spoiler => {
+            if let Some(x) = &node.spoiler_node {
+                out.push_str(&format!("||{}||", str_or_empty(&x.content)));
+            }
+        }
+        V1NodeType::HtmlElement => {
+            if let Some(x) = &node.html_element_node {
+                out.push('<');
+                out.push_str(str_or_empty(&x.tag_name));
+                let mut attributes = x.attributes.iter().flatten().collect::<Vec<_>>();
+                attributes.sort();
+                for (name, value) in attributes {
+                    out.push_str(&format!(" {}=\"{}\"", name, value));
+                }
+                out.push_str(" />");
+            }
+        }
+        V1NodeType::NodeUnspecified => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::markdown::parser::parse;
+
+    #[test]
+    fn test_restore() {
+        for content in [
+            "# Title #tag\n\nSome **bold _it_** and ~~old~~ <https://x.y> ![a](b.png)",
+            "```rust\nfn a() {}\n```\n> a\n> > b\n---",
+            "- [ ] todo\n  1. one\n  2. two\n* [x] done\n\n| a | b |\n| --- | :-: |\n| 1 | [l](u) |",
+            "$$\nx\n$$\n![[resources/1?align=center]] [[memos/2]] ||s|| ==h== \\* $m$ H~2~O x^2^<br />",
+        ] {
+            assert_eq!(restore(&parse(content)), content);
+        }
+        assert_eq!(restore(&parse("a<br>b<br/>")), "a<br />b<br />");
+    }
+}
@@ -0,0 +1,286 @@
+use std::{
+    collections::HashSet,
+    path::{Path, PathBuf},
+};
+
+use dashmap::DashMap;
+use parking_lot::Mutex;
+
+use crate::{
+    store::{
+        backend::{KvStore, MemoStore},
+        model::{Memo, MemoMeta, StoreData},
+        schema,
+        secret::{SECRET_KEYS, SecretData, open_store_data, seal_store_data},
+    },
+    utils::{file::copies_of, path::sanitize_file_name},
+};
+
+/// JSON files under the app cache dir:
+/// `{account}/memos/memo_{name}.json` and `{account}/memos/all_memo_meta.json`.
+/// Superseded by `SqliteMemoStore`, only read to migrate old caches.
+#[derive(Debug, Clone)]
+pub struct FsMemoStore {
+    root: PathBuf,
+}
+
+impl FsMemoStore {
+    pub fn new(root: PathBuf) -> Self {
+        Self { root }
+    }
+
+    fn memos_dir(&self, account: &str) -> PathBuf {
+        self.root.join(account).join("memos")
+    }
+
+    fn memo_path(&self, account: &str, memo_name: &str) -> PathBuf {
+        self.memos_dir(account)
+            .join("memo_".to_string() + &sanitize_file_name(memo_name) + ".json")
+    }
+
+    /// Accounts that have memos cached in JSON files.
+    pub fn accounts(&self) -> Vec<String> {
+        let Ok(dirs) = std::fs::read_dir(&self.root) else {
+            return Vec::new();
+        };
+
+        dirs.flatten()
+            .filter(|x| x.path().join("memos").join("all_memo_meta.json").exists())
+            .filter_map(|x| x.file_name().into_string().ok())
+            .collect()
+    }
+
+    /// Deletes `{account}/memos`: the memo files, the metadata and the sync
+    /// meta, so the next sync is a full one. Anything else under `{account}`
+    /// is kept, an `outbox.json` included.
+    pub fn remove_account(&self, account: &str) -> Result<(), String> {
+        std::fs::remove_dir_all(self.memos_dir(account))
+            .map_err(|e| format!("Failed to delete memo cache dir: {}", e))
+    }
+
+    fn write(&self, account: &str, path: &Path, content: String) -> Result<(), String> {
+        std::fs::create_dir_all(self.memos_dir(account))
+            .map_err(|e| format!("Failed to create memo cache dir: {}", e))?;
+        std::fs::write(path, content).map_err(|e| format!("Failed to write memo cache: {}", e))
+    }
+}
+
+impl MemoStore for FsMemoStore {
+    fn load_memo(&self, account: &str, memo_name: &str) -> Result<Option<Memo>, String> {
+        let path = self.memo_path(account, memo_name);
+        if !path.exists() {
+            return Ok(None);
+        }
+
+        let content = std::fs::read_to_string(&path)
+            .map_err(|e| format!("Failed to read memo cache: {}", e))?;
+
+        serde_json::from_str(&content)
+            .map(Some)
+            .map_err(|e| format!("Failed to parse memo cache: {}", e))
+    }
+
+    fn save_memo(&self, account: &str, memo: &Memo) -> Result<(), String> {
+        let content = serde_json::to_string(memo)
+            .map_err(|e| format!("Failed to serialize memo cache: {}", e))?;
+        self.write(account, &self.memo_path(account, &memo.name), content)
+    }
+
+    fn remove_memo(&self, account: &str, memo_name: &str) -> Result<(), String> {
+        match std::fs::remove_file(self.memo_path(account, memo_name)) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => {
+                return Err(format!("Failed to delete memo cache: {}", e));
+            }
+            _ => {}
+        }
+
+        let meta = self.load_meta(account)?;
+        if meta.remove(memo_name).is_some() {
+            self.save_meta(account, &meta, &HashSet::new())?;
+        }
+        Ok(())
+    }
+
+    fn load_meta(&self, account: &str) -> Result<DashMap<String, MemoMeta>, String> {
+        let path = self.memos_dir(account).join("all_memo_meta.json");
+        if !path.exists() {
+            return Ok(DashMap::new());
+        }
+
+        let content = std::fs::read_to_string(&path)
+            .map_err(|e| format!("Failed to read get memo metadata cache: {}", e))?;
+
+        serde_json::from_str(&content)
+            .map_err(|e| format!("Failed to parse get memo metadata cache: {}", e))
+    }
+
+    fn save_meta(
+        &self,
+        account: &str,
+        meta: &DashMap<String, MemoMeta>,
+        _changed: &HashSet<String>,
+    ) -> Result<(), String> {
+        let content = serde_json::to_string(meta)
+            .map_err(|e| format!("Failed to serialize all_memo_meta cache: {}", e))?;
+        let path = self.memos_dir(account).join("all_memo_meta.json");
+        self.write(account, &path, content)
+    }
+}
+
+/// `store.json` under the app data dir, with `SECRET_KEYS` sealed into
+/// `secrets.json` next to it. Both are versioned by `schema`.
+#[derive(Debug)]
+pub struct FsKvStore {
+    dir: PathBuf,
+    writer: Mutex<()>,
+}
+
+impl FsKvStore {
+    pub fn new(dir: PathBuf) -> Self {
+        Self {
+            dir,
+            writer: Mutex::new(()),
+        }
+    }
+
+    fn store_path(&self) -> PathBuf {
+        self.dir.join("store.json")
+    }
+
+    fn secrets_path(&self) -> PathBuf {
+        self.dir.join("secrets.json")
+    }
+
+    /// Serializes the store with `SECRET_KEYS` moved into a sealed
+    /// `SecretData`.
+    fn seal(&self, store_data: &StoreData) -> Result<(String, String), String> {
+        let mut value = serde_json::to_value(store_data)
+            .map_err(|e| format!("Failed to serialize store data: {}", e))?;
+        let secrets = seal_store_data(&self.dir, &mut value)?;
+
+        let content = serde_json::to_string(&value)
+            .map_err(|e| format!("Failed to serialize store data: {}", e))?;
+        let secrets = serde_json::to_string(&secrets)
+            .map_err(|e| format!("Failed to serialize secrets: {}", e))?;
+        Ok((content, secrets))
+    }
+
+    /// Deletes old copies of `store.json` that still hold plaintext secrets.
+    fn remove_plaintext_copies(&self) {
+        for path in copies_of(&self.store_path()) {
+            let Ok(content) = std::fs::read_to_string(&path) else {
+                continue;
+            };
+            if SECRET_KEYS
+                .iter()
+                .any(|k| content.contains(&format!("\"{}\"", k)))
+            {
+                log::info!("Removing plaintext secrets in {}", path.display());
+                if let Err(e) = std::fs::remove_file(&path) {
+                    log::error!("Failed to remove {}: {}", path.display(), e);
+                }
+            }
+        }
+    }
+
+    /// Puts the secrets sealed by `seal` back into the store.
+    fn open(&self, store_data: &StoreData) -> Result<(), String> {
+        match schema::SECRETS.read::<SecretData>(&self.secrets_path())? {
+            Some(secrets) => open_store_data(&self.dir, secrets, store_data),
+            None => Ok(()),
+        }
+    }
+}
+
+impl KvStore for FsKvStore {
+    fn load(&self) -> Result<Option<StoreData>, String> {
+        // Upgraded by the save below, writing it as read would keep plaintext
+        // secrets on disk.
+        let Some((version, store_data)) =
+            schema::STORE.read_versioned::<StoreData>(&self.store_path())?
+        else {
+            return Ok(None);
+        };
+
+        // Stores written before secrets were sealed keep tokens in plaintext.
+        let has_plaintext = SECRET_KEYS.iter().any(|k| {
+            store_data.data.contains_key(*k)
+                || store_data
+                    .profiles
+                    .read()
+                    .iter()
+                    .any(|x| x.data.contains_key(*k))
+        });
+        if let Err(e) = self.open(&store_data) {
+            log::error!("Failed to load secrets, login required: {}", e);
+        }
+        let mut sealed = true;
+        if has_plaintext || version < schema::STORE.version() {
+            log::info!(
+                "Upgrading the store file, plaintext secrets: {}",
+                has_plaintext
+            );
+            if let Err(e) = self.save(&store_data) {
+                log::error!("Failed to upgrade the store file: {}", e);
+                sealed = false;
+            }
+        }
+        // Copies from before the upgrade, including ones earlier versions
+        // left behind.
+        if sealed {
+            self.remove_plaintext_copies();
+        }
+
+        Ok(Some(store_data))
+    }
+
+    fn save(&self, data: &StoreData) -> Result<(), String> {
+        // Snapshot and write under one lock, so the last call wins on disk.
+        let _writer = self.writer.lock();
+
+        std::fs::create_dir_all(&self.dir)
+            .map_err(|e| format!("Failed to create app data dir: {}", e))?;
+
+        let (content, secrets) = self.seal(data)?;
+
+        // Secrets first, a crash in between must not lose the token.
+        schema::SECRETS
+            .write_str(&self.secrets_path(), &secrets)
+            .map_err(|e| format!("Failed to write secrets file: {}", e))?;
+
+        schema::STORE
+            .write_str(&self.store_path(), &content)
+            .map_err(|e| format!("Failed to write store file: {}", e))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_seal_plaintext_store() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = StoreData::default();
+        store
+            .data
+            .insert("accessToken".to_string(), "plaintext-token".to_string());
+        let legacy = serde_json::to_string(&store).unwrap();
+        std::fs::write(dir.path().join("store.json"), &legacy).unwrap();
+        std::fs::write(dir.path().join("store.json.corrupt"), &legacy).unwrap();
+
+        let kv_store = FsKvStore::new(dir.path().to_path_buf());
+        let loaded = kv_store.load().unwrap().unwrap();
+        assert_eq!(
+            loaded.data.get("accessToken").map(|v| v.clone()),
+            Some("plaintext-token".to_string())
+        );
+        for entry in std::fs::read_dir(dir.path()).unwrap() {
+            let content = std::fs::read(entry.unwrap().path()).unwrap();
+            assert!(!String::from_utf8_lossy(&content).contains("plaintext-token"));
+        }
+
+        let loaded = kv_store.load().unwrap().unwrap();
+        assert!(loaded.data.contains_key("accessToken"));
+    }
+}
@@ -0,0 +1,131 @@
+use std::collections::HashSet;
+
+use dashmap::DashMap;
+use parking_lot::RwLock;
+
+use crate::store::{
+    backend::{KvStore, MemoStore},
+    model::{Memo, MemoMeta, StoreData},
+    search::{Postings, is_text_term, term_counts},
+};
+
+/// Keeps everything in memory, for tests. Values are stored serialized so
+/// they go through the same serde round trip as on disk.
+#[derive(Debug, Default)]
+pub struct MemoryMemoStore {
+    memos: DashMap<(String, String), String>,
+    meta: DashMap<String, String>,
+}
+
+impl MemoStore for MemoryMemoStore {
+    fn load_memo(&self, account: &str, memo_name: &str) -> Result<Option<Memo>, String> {
+        let key = (account.to_string(), memo_name.to_string());
+        match self.memos.get(&key) {
+            Some(content) => serde_json::from_str(&content)
+                .map(Some)
+                .map_err(|e| format!("Failed to parse memo cache: {}", e)),
+            None => Ok(None),
+        }
+    }
+
+    fn save_memo(&self, account: &str, memo: &Memo) -> Result<(), String> {
+        let content = serde_json::to_string(memo)
+            .map_err(|e| format!("Failed to serialize memo cache: {}", e))?;
+        self.memos
+            .insert((account.to_string(), memo.name.clone()), content);
+        Ok(())
+    }
+
+    fn remove_memo(&self, account: &str, memo_name: &str) -> Result<(), String> {
+        self.memos
+            .remove(&(account.to_string(), memo_name.to_string()));
+        let meta = self.load_meta(account)?;
+        if meta.remove(memo_name).is_some() {
+            self.save_meta(account, &meta, &HashSet::new())?;
+        }
+        Ok(())
+    }
+
+    fn load_meta(&self, account: &str) -> Result<DashMap<String, MemoMeta>, String> {
+        match self.meta.get(account) {
+            Some(content) => serde_json::from_str(&content)
+                .map_err(|e| format!("Failed to parse get memo metadata cache: {}", e)),
+            None => Ok(DashMap::new()),
+        }
+    }
+
+    fn save_meta(
+        &self,
+        account: &str,
+        meta: &DashMap<String, MemoMeta>,
+        _changed: &HashSet<String>,
+    ) -> Result<(), String> {
+        let content = serde_json::to_string(meta)
+            .map_err(|e| format!("Failed to serialize all_memo_meta cache: {}", e))?;
+        self.meta.insert(account.to_string(), content);
+        Ok(())
+    }
+
+    fn load_postings(&self, account: &str, terms: &[String]) -> Result<Postings, String> {
+        let mut postings = Postings::default();
+        for x in self.memos.iter().filter(|x| x.key().0 == account) {
+            let memo = serde_json::from_str::<Memo>(x.value())
+                .map_err(|e| format!("Failed to parse memo cache: {}", e))?;
+            let counts = term_counts(&memo.content);
+            let length = counts.values().sum::<u32>();
+            postings.memo_count += 1;
+            postings.total_terms += length as u64;
+            for term in terms {
+                if let Some(count) = counts.get(term) {
+                    postings
+                        .terms
+                        .entry(term.clone())
+                        .or_default()
+                        .insert(memo.name.clone(), *count);
+                    postings.lengths.insert(memo.name.clone(), length);
+                }
+            }
+        }
+        Ok(postings)
+    }
+
+    fn load_terms(
+        &self,
+        account: &str,
+        min_chars: usize,
+        max_chars: usize,
+    ) -> Result<Vec<String>, String> {
+        let mut terms = HashSet::new();
+        for x in self.memos.iter().filter(|x| x.key().0 == account) {
+            let memo = serde_json::from_str::<Memo>(x.value())
+                .map_err(|e| format!("Failed to parse memo cache: {}", e))?;
+            terms.extend(term_counts(&memo.content).into_keys().filter(|x| {
+                is_text_term(x) && (min_chars..=max_chars).contains(&x.chars().count())
+            }));
+        }
+        Ok(terms.into_iter().collect())
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct MemoryKvStore {
+    content: RwLock<Option<String>>,
+}
+
+impl KvStore for MemoryKvStore {
+    fn load(&self) -> Result<Option<StoreData>, String> {
+        match &*self.content.read() {
+            Some(content) => serde_json::from_str(content)
+                .map(Some)
+                .map_err(|e| format!("Failed to parse store data: {}", e)),
+            None => Ok(None),
+        }
+    }
+
+    fn save(&self, data: &StoreData) -> Result<(), String> {
+        let content = serde_json::to_string(data)
+            .map_err(|e| format!("Failed to serialize store data: {}", e))?;
+        *self.content.write() = Some(content);
+        Ok(())
+    }
+}
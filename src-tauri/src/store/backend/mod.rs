@@ -0,0 +1,74 @@
+use std::collections::{HashMap, HashSet};
+
+use dashmap::DashMap;
+
+use crate::store::{
+    model::{Memo, MemoMeta, StoreData},
+    search::Postings,
+};
+
+pub mod fs;
+pub mod memory;
+pub mod sqlite;
+
+/// Persistent storage of the memo cache. Memos are grouped by account, the
+/// profile id from `get_profile_id`.
+pub trait MemoStore: Send + Sync + std::fmt::Debug {
+    fn load_memo(&self, account: &str, memo_name: &str) -> Result<Option<Memo>, String>;
+
+    /// The found memos by name.
+    fn load_memos(
+        &self,
+        account: &str,
+        memo_names: &[String],
+    ) -> Result<HashMap<String, Memo>, String> {
+        let mut memos = HashMap::with_capacity(memo_names.len());
+        for name in memo_names {
+            if let Some(memo) = self.load_memo(account, name)? {
+                memos.insert(name.clone(), memo);
+            }
+        }
+        Ok(memos)
+    }
+
+    fn save_memo(&self, account: &str, memo: &Memo) -> Result<(), String>;
+
+    /// Deletes the memo together with its metadata row, so the stored
+    /// metadata never lists a memo that is gone.
+    fn remove_memo(&self, account: &str, memo_name: &str) -> Result<(), String>;
+
+    fn load_meta(&self, account: &str) -> Result<DashMap<String, MemoMeta>, String>;
+
+    /// Writes the metadata of the `changed` names, the ones missing from
+    /// `meta` are deleted. Stores without rows may write all of `meta`.
+    fn save_meta(
+        &self,
+        account: &str,
+        meta: &DashMap<String, MemoMeta>,
+        changed: &HashSet<String>,
+    ) -> Result<(), String>;
+
+    /// The full-text index entries of `terms`, see `search`.
+    fn load_postings(&self, _account: &str, _terms: &[String]) -> Result<Postings, String> {
+        Err("Search is not supported by this memo store".to_string())
+    }
+
+    /// Indexed Latin and CJK terms of `min_chars..=max_chars` characters,
+    /// for fuzzy search.
+    fn load_terms(
+        &self,
+        _account: &str,
+        _min_chars: usize,
+        _max_chars: usize,
+    ) -> Result<Vec<String>, String> {
+        Err("Search is not supported by this memo store".to_string())
+    }
+}
+
+/// Persistent storage of the frontend key value store.
+pub trait KvStore: Send + Sync + std::fmt::Debug {
+    /// `None` on first start.
+    fn load(&self) -> Result<Option<StoreData>, String>;
+
+    fn save(&self, data: &StoreData) -> Result<(), String>;
+}
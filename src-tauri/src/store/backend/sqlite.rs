@@ -0,0 +1,717 @@
+use std::{
+    collections::{HashMap, HashSet},
+    path::Path,
+};
+
+use dashmap::DashMap;
+use parking_lot::Mutex;
+use rusqlite::{Connection, OptionalExtension, params};
+
+use crate::store::{
+    backend::{MemoStore, fs::FsMemoStore},
+    model::{Memo, MemoMeta},
+    schema,
+    search::{self, Postings, term_counts},
+};
+
+const SCHEMA_V1: &str = "
+CREATE TABLE IF NOT EXISTS memo (
+    account TEXT NOT NULL,
+    name TEXT NOT NULL,
+    data TEXT NOT NULL,
+    PRIMARY KEY (account, name)
+);
+CREATE TABLE IF NOT EXISTS memo_meta (
+    account TEXT NOT NULL,
+    name TEXT NOT NULL,
+    create_time TEXT NOT NULL,
+    update_time TEXT NOT NULL,
+    display_time TEXT NOT NULL,
+    visibility TEXT NOT NULL,
+    pinned INTEGER NOT NULL,
+    state TEXT NOT NULL,
+    PRIMARY KEY (account, name)
+);
+CREATE TABLE IF NOT EXISTS memo_tag (
+    account TEXT NOT NULL,
+    memo_name TEXT NOT NULL,
+    position INTEGER NOT NULL,
+    tag TEXT NOT NULL,
+    PRIMARY KEY (account, memo_name, position)
+);
+CREATE INDEX IF NOT EXISTS memo_tag_tag ON memo_tag (account, tag);
+CREATE TABLE IF NOT EXISTS memo_resource (
+    account TEXT NOT NULL,
+    memo_name TEXT NOT NULL,
+    position INTEGER NOT NULL,
+    name TEXT,
+    filename TEXT,
+    type TEXT,
+    size TEXT,
+    external_link TEXT,
+    PRIMARY KEY (account, memo_name, position)
+);
+CREATE TABLE IF NOT EXISTS memo_relation (
+    account TEXT NOT NULL,
+    memo_name TEXT NOT NULL,
+    related_memo TEXT NOT NULL,
+    type TEXT NOT NULL,
+    PRIMARY KEY (account, memo_name, related_memo, type)
+);
+CREATE TABLE IF NOT EXISTS json_migration (
+    account TEXT PRIMARY KEY,
+    memo_count INTEGER NOT NULL
+);
+";
+
+/// Database layout upgrades, `PRAGMA user_version` counts the applied ones.
+const MIGRATIONS: &[&str] = &[
+    SCHEMA_V1,
+    // Schema version of the memo JSON, see `schema::MEMO`.
+    "ALTER TABLE memo ADD COLUMN version INTEGER NOT NULL DEFAULT 0;",
+    // Full-text index, see `search`. Memos are indexed by `upgrade_memos`.
+    "CREATE TABLE IF NOT EXISTS memo_term (
+        account TEXT NOT NULL,
+        term TEXT NOT NULL,
+        memo_name TEXT NOT NULL,
+        frequency INTEGER NOT NULL,
+        PRIMARY KEY (account, term, memo_name)
+    ) WITHOUT ROWID;
+    CREATE INDEX IF NOT EXISTS memo_term_memo ON memo_term (account, memo_name);
+    ALTER TABLE memo ADD COLUMN term_count INTEGER NOT NULL DEFAULT 0;
+    ALTER TABLE memo ADD COLUMN index_version INTEGER NOT NULL DEFAULT 0;",
+];
+
+/// One SQLite database for the memo cache of all accounts. `memo` keeps the
+/// full memo as JSON, the other tables are its queryable parts.
+#[derive(Debug)]
+pub struct SqliteMemoStore {
+    conn: Mutex<Connection>,
+}
+
+impl SqliteMemoStore {
+    pub fn open(path: &Path) -> Result<Self, String> {
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)
+                .map_err(|e| format!("Failed to create memo database dir: {}", e))?;
+        }
+
+        let conn =
+            Connection::open(path).map_err(|e| format!("Failed to open memo database: {}", e))?;
+        conn.pragma_update(None, "journal_mode", "WAL")
+            .map_err(|e| format!("Failed to set memo database journal mode: {}", e))?;
+        Self::init(conn)
+    }
+
+    #[cfg(test)]
+    pub fn open_in_memory() -> Result<Self, String> {
+        let conn = Connection::open_in_memory()
+            .map_err(|e| format!("Failed to open memo database: {}", e))?;
+        Self::init(conn)
+    }
+
+    fn init(mut conn: Connection) -> Result<Self, String> {
+        migrate(&mut conn)?;
+        upgrade_memos(&mut conn)?;
+        Ok(Self {
+            conn: Mutex::new(conn),
+        })
+    }
+
+    /// Imports the JSON files written by `FsMemoStore`, once per account, and
+    /// removes them afterwards.
+    pub fn migrate_from(&self, fs: &FsMemoStore) -> Result<(), String> {
+        for account in fs.accounts() {
+            let migrated = self
+                .conn
+                .lock()
+                .query_row(
+                    "SELECT 1 FROM json_migration WHERE account = ?1",
+                    params![account],
+                    |_| Ok(()),
+                )
+                .optional()
+                .map_err(|e| format!("Failed to read memo database: {}", e))?
+                .is_some();
+
+            if !migrated {
+                let meta = fs.load_meta(&account)?;
+                let mut memos = Vec::with_capacity(meta.len());
+                for x in meta.iter() {
+                    match fs.load_memo(&account, x.key()) {
+                        Ok(Some(memo)) => memos.push(memo),
+                        Ok(None) => {}
+                        Err(e) => log::warn!("Skipping memo {} in migration: {}", x.key(), e),
+                    }
+                }
+
+                let mut conn = self.conn.lock();
+                let tx = conn
+                    .transaction()
+                    .map_err(|e| format!("Failed to begin memo database transaction: {}", e))?;
+                put_meta(&tx, &account, &meta)?;
+                for memo in &memos {
+                    put_memo(&tx, &account, memo)?;
+                }
+                tx.execute(
+                    "INSERT INTO json_migration (account, memo_count) VALUES (?1, ?2)",
+                    params![account, memos.len()],
+                )
+                .map_err(|e| format!("Failed to write memo database: {}", e))?;
+                tx.commit()
+                    .map_err(|e| format!("Failed to commit memo database: {}", e))?;
+
+                log::info!(
+                    "Migrated {} memos of {} into the memo database",
+                    memos.len(),
+                    account
+                );
+            }
+
+            fs.remove_account(&account)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl MemoStore for SqliteMemoStore {
+    fn load_memo(&self, account: &str, memo_name: &str) -> Result<Option<Memo>, String> {
+        let data = self
+            .conn
+            .lock()
+            .query_row(
+                "SELECT version, data FROM memo WHERE account = ?1 AND name = ?2",
+                params![account, memo_name],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .optional()
+            .map_err(|e| format!("Failed to read memo cache: {}", e))?;
+
+        data.map(|(version, data): (u32, String)| schema::MEMO.parse(version, &data))
+            .transpose()
+    }
+
+    fn load_memos(
+        &self,
+        account: &str,
+        memo_names: &[String],
+    ) -> Result<HashMap<String, Memo>, String> {
+        let map_err = |e: rusqlite::Error| format!("Failed to read memo cache: {}", e);
+        let names = serde_json::to_string(memo_names)
+            .map_err(|e| format!("Failed to serialize memo names: {}", e))?;
+
+        let conn = self.conn.lock();
+        let mut stmt = conn
+            .prepare_cached(
+                "SELECT name, version, data FROM memo
+                WHERE account = ?1 AND name IN (SELECT value FROM json_each(?2))",
+            )
+            .map_err(map_err)?;
+        let rows = stmt
+            .query_map(params![account, names], |row| {
+                Ok((
+                    row.get::<_, String>(0)?,
+                    row.get::<_, u32>(1)?,
+                    row.get::<_, String>(2)?,
+                ))
+            })
+            .map_err(map_err)?;
+
+        let mut memos = HashMap::with_capacity(memo_names.len());
+        for row in rows {
+            let (name, version, data) = row.map_err(map_err)?;
+            memos.insert(name, schema::MEMO.parse(version, &data)?);
+        }
+        Ok(memos)
+    }
+
+    fn save_memo(&self, account: &str, memo: &Memo) -> Result<(), String> {
+        let mut conn = self.conn.lock();
+        let tx = conn
+            .transaction()
+            .map_err(|e| format!("Failed to begin memo database transaction: {}", e))?;
+        put_memo(&tx, account, memo)?;
+        tx.commit()
+            .map_err(|e| format!("Failed to commit memo cache: {}", e))
+    }
+
+    fn remove_memo(&self, account: &str, memo_name: &str) -> Result<(), String> {
+        let mut conn = self.conn.lock();
+        let tx = conn
+            .transaction()
+            .map_err(|e| format!("Failed to begin memo database transaction: {}", e))?;
+        delete_memo(&tx, account, memo_name)?;
+        delete_meta(&tx, account, memo_name)?;
+        tx.commit()
+            .map_err(|e| format!("Failed to delete memo cache: {}", e))
+    }
+
+    fn load_meta(&self, account: &str) -> Result<DashMap<String, MemoMeta>, String> {
+        read_meta(&self.conn.lock(), account)
+    }
+
+    fn save_meta(
+        &self,
+        account: &str,
+        meta: &DashMap<String, MemoMeta>,
+        changed: &HashSet<String>,
+    ) -> Result<(), String> {
+        let mut conn = self.conn.lock();
+        let tx = conn
+            .transaction()
+            .map_err(|e| format!("Failed to begin memo database transaction: {}", e))?;
+
+        let rows = DashMap::new();
+        for name in changed {
+            match meta.get(name) {
+                Some(x) => {
+                    rows.insert(name.clone(), x.value().clone());
+                }
+                None => delete_meta(&tx, account, name)?,
+            }
+        }
+        put_meta(&tx, account, &rows)?;
+
+        tx.commit()
+            .map_err(|e| format!("Failed to commit all_memo_meta cache: {}", e))
+    }
+
+    fn load_postings(&self, account: &str, terms: &[String]) -> Result<Postings, String> {
+        let map_err = |e: rusqlite::Error| format!("Failed to read search index: {}", e);
+        let terms = serde_json::to_string(terms)
+            .map_err(|e| format!("Failed to serialize search terms: {}", e))?;
+
+        let conn = self.conn.lock();
+        let mut postings = Postings::default();
+        (postings.memo_count, postings.total_terms) = conn
+            .query_row(
+                "SELECT COUNT(*), COALESCE(SUM(term_count), 0) FROM memo WHERE account = ?1",
+                params![account],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .map_err(map_err)?;
+
+        let mut stmt = conn
+            .prepare_cached(
+                "SELECT t.term, t.memo_name, t.frequency, m.term_count
+                FROM memo_term t JOIN memo m ON m.account = t.account AND m.name = t.memo_name
+                WHERE t.account = ?1 AND t.term IN (SELECT value FROM json_each(?2))",
+            )
+            .map_err(map_err)?;
+        let rows = stmt
+            .query_map(params![account, terms], |row| {
+                Ok((
+                    row.get::<_, String>(0)?,
+                    row.get::<_, String>(1)?,
+                    row.get::<_, u32>(2)?,
+                    row.get::<_, u32>(3)?,
+                ))
+            })
+            .map_err(map_err)?;
+        for row in rows {
+            let (term, memo_name, frequency, length) = row.map_err(map_err)?;
+            postings.lengths.insert(memo_name.clone(), length);
+            postings
+                .terms
+                .entry(term)
+                .or_default()
+                .insert(memo_name, frequency);
+        }
+        Ok(postings)
+    }
+
+    fn load_terms(
+        &self,
+        account: &str,
+        min_chars: usize,
+        max_chars: usize,
+    ) -> Result<Vec<String>, String> {
+        let map_err = |e: rusqlite::Error| format!("Failed to read search index: {}", e);
+        let conn = self.conn.lock();
+        let mut stmt = conn
+            .prepare_cached(
+                "SELECT DISTINCT term FROM memo_term
+                WHERE account = ?1 AND length(term) BETWEEN ?2 AND ?3",
+            )
+            .map_err(map_err)?;
+        let rows = stmt
+            .query_map(params![account, min_chars, max_chars], |row| {
+                row.get::<_, String>(0)
+            })
+            .map_err(map_err)?;
+        let mut terms = Vec::new();
+        for term in rows {
+            let term = term.map_err(map_err)?;
+            if search::is_text_term(&term) {
+                terms.push(term);
+            }
+        }
+        Ok(terms)
+    }
+}
+
+fn migrate(conn: &mut Connection) -> Result<(), String> {
+    let version: usize = conn
+        .pragma_query_value(None, "user_version", |row| row.get(0))
+        .map_err(|e| format!("Failed to read memo database version: {}", e))?;
+    if version > MIGRATIONS.len() {
+        return Err(format!(
+            "Memo database version {} is newer than the supported {}",
+            version,
+            MIGRATIONS.len()
+        ));
+    }
+
+    for (i, sql) in MIGRATIONS.iter().enumerate().skip(version) {
+        log::info!("Upgrading memo database to version {}", i + 1);
+        let map_err = |e: rusqlite::Error| {
+            format!(
+                "Failed to upgrade memo database to version {}: {}",
+                i + 1,
+                e
+            )
+        };
+
+        let tx = conn.transaction().map_err(map_err)?;
+        tx.execute_batch(sql).map_err(map_err)?;
+        tx.pragma_update(None, "user_version", i + 1)
+            .map_err(map_err)?;
+        tx.commit().map_err(map_err)?;
+    }
+
+    Ok(())
+}
+
+/// Rewrites memos stored with an older `schema::MEMO` version or indexed with
+/// another `search::INDEX_VERSION`. Memos that fail to upgrade are logged
+/// and left for `load_memo` to report.
+fn upgrade_memos(conn: &mut Connection) -> Result<(), String> {
+    let map_err = |e: rusqlite::Error| format!("Failed to upgrade memo cache: {}", e);
+
+    let tx = conn.transaction().map_err(map_err)?;
+    let rows = {
+        let mut stmt = tx
+            .prepare(
+                "SELECT account, name, version, data FROM memo
+                WHERE version < ?1 OR index_version != ?2",
+            )
+            .map_err(map_err)?;
+        stmt.query_map(
+            params![schema::MEMO.version(), search::INDEX_VERSION],
+            |row| {
+                Ok((
+                    row.get::<_, String>(0)?,
+                    row.get::<_, String>(1)?,
+                    row.get::<_, u32>(2)?,
+                    row.get::<_, String>(3)?,
+                ))
+            },
+        )
+        .map_err(map_err)?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(map_err)?
+    };
+    if rows.is_empty() {
+        return Ok(());
+    }
+
+    log::info!(
+        "Upgrading {} memos to version {}, index version {}",
+        rows.len(),
+        schema::MEMO.version(),
+        search::INDEX_VERSION
+    );
+    for (account, name, version, data) in rows {
+        match schema::MEMO.parse::<Memo>(version, &data) {
+            Ok(memo) => put_memo(&tx, &account, &memo)?,
+            Err(e) => log::error!("Failed to upgrade memo {}: {}", name, e),
+        }
+    }
+
+    tx.commit().map_err(map_err)
+}
+
+fn read_meta(conn: &Connection, account: &str) -> Result<DashMap<String, MemoMeta>, String> {
+    let map_err = |e: rusqlite::Error| format!("Failed to read memo metadata cache: {}", e);
+
+    let mut stmt = conn
+        .prepare(
+            "SELECT name, create_time, update_time, display_time, visibility, pinned, state
+            FROM memo_meta WHERE account = ?1",
+        )
+        .map_err(map_err)?;
+    let rows = stmt
+        .query_map(params![account], |row| {
+            let state: String = row.get(6)?;
+            Ok(MemoMeta {
+                name: row.get(0)?,
+                create_time: row.get(1)?,
+                update_time: row.get(2)?,
+                display_time: row.get(3)?,
+                visibility: row.get(4)?,
+                pinned: row.get(5)?,
+                tags: Vec::new(),
+                state: serde_json::from_value(serde_json::Value::String(state)).unwrap_or_default(),
+            })
+        })
+        .map_err(map_err)?;
+
+    let meta = DashMap::new();
+    for row in rows {
+        let row = row.map_err(map_err)?;
+        meta.insert(row.name.clone(), row);
+    }
+
+    let mut stmt = conn
+        .prepare("SELECT memo_name, tag FROM memo_tag WHERE account = ?1 ORDER BY position")
+        .map_err(map_err)?;
+    let rows = stmt
+        .query_map(params![account], |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+        })
+        .map_err(map_err)?;
+    for row in rows {
+        let (memo_name, tag) = row.map_err(map_err)?;
+        if let Some(mut x) = meta.get_mut(&memo_name) {
+            x.tags.push(tag);
+        }
+    }
+
+    Ok(meta)
+}
+
+fn put_meta(
+    conn: &Connection,
+    account: &str,
+    meta: &DashMap<String, MemoMeta>,
+) -> Result<(), String> {
+    let map_err = |e: rusqlite::Error| format!("Failed to write memo metadata cache: {}", e);
+
+    let mut put = conn
+        .prepare_cached(
+            "INSERT OR REPLACE INTO memo_meta
+            (account, name, create_time, update_time, display_time, visibility, pinned, state)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+        )
+        .map_err(map_err)?;
+    let mut clear_tags = conn
+        .prepare_cached("DELETE FROM memo_tag WHERE account = ?1 AND memo_name = ?2")
+        .map_err(map_err)?;
+    let mut put_tag = conn
+        .prepare_cached(
+            "INSERT INTO memo_tag (account, memo_name, position, tag) VALUES (?1, ?2, ?3, ?4)",
+        )
+        .map_err(map_err)?;
+
+    for x in meta.iter() {
+        put.execute(params![
+            account,
+            x.key(),
+            x.create_time,
+            x.update_time,
+            x.display_time,
+            x.visibility,
+            x.pinned,
+            x.state.to_string(),
+        ])
+        .map_err(map_err)?;
+
+        clear_tags
+            .execute(params![account, x.key()])
+            .map_err(map_err)?;
+        for (i, tag) in x.tags.iter().enumerate() {
+            put_tag
+                .execute(params![account, x.key(), i, tag])
+                .map_err(map_err)?;
+        }
+    }
+
+    Ok(())
+}
+
+fn delete_meta(conn: &Connection, account: &str, memo_name: &str) -> Result<(), String> {
+    for sql in [
+        "DELETE FROM memo_meta WHERE account = ?1 AND name = ?2",
+        "DELETE FROM memo_tag WHERE account = ?1 AND memo_name = ?2",
+    ] {
+        conn.execute(sql, params![account, memo_name])
+            .map_err(|e| format!("Failed to delete memo metadata cache: {}", e))?;
+    }
+    Ok(())
+}
+
+fn put_memo(conn: &Connection, account: &str, memo: &Memo) -> Result<(), String> {
+    let map_err = |e: rusqlite::Error| format!("Failed to write memo cache: {}", e);
+
+    let data = serde_json::to_string(memo)
+        .map_err(|e| format!("Failed to serialize memo cache: {}", e))?;
+
+    delete_memo(conn, account, &memo.name)?;
+    let counts = term_counts(&memo.content);
+    conn.execute(
+        "INSERT INTO memo (account, name, version, data, term_count, index_version)
+        VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+        params![
+            account,
+            memo.name,
+            schema::MEMO.version(),
+            data,
+            counts.values().sum::<u32>(),
+            search::INDEX_VERSION,
+        ],
+    )
+    .map_err(map_err)?;
+
+    let mut put_term = conn
+        .prepare_cached(
+            "INSERT INTO memo_term (account, term, memo_name, frequency) VALUES (?1, ?2, ?3, ?4)",
+        )
+        .map_err(map_err)?;
+    for (term, frequency) in &counts {
+        put_term
+            .execute(params![account, term, memo.name, frequency])
+            .map_err(map_err)?;
+    }
+
+    let mut put_resource = conn
+        .prepare_cached(
+            "INSERT INTO memo_resource
+            (account, memo_name, position, name, filename, type, size, external_link)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+        )
+        .map_err(map_err)?;
+    for (i, x) in memo.resources.iter().enumerate() {
+        put_resource
+            .execute(params![
+                account,
+                memo.name,
+                i,
+                x.name,
+                x.filename,
+                x.r#type,
+                x.size,
+                x.external_link,
+            ])
+            .map_err(map_err)?;
+    }
+
+    let mut put_relation = conn
+        .prepare_cached(
+            "INSERT OR IGNORE INTO memo_relation (account, memo_name, related_memo, type)
+            VALUES (?1, ?2, ?3, ?4)",
+        )
+        .map_err(map_err)?;
+    for x in &memo.relations {
+        let Some(related_memo) = x.related_memo.as_ref().and_then(|x| x.name.as_ref()) else {
+            continue;
+        };
+        put_relation
+            .execute(params![
+                account,
+                memo.name,
+                related_memo,
+                x.r#type.unwrap_or_default().to_string(),
+            ])
+            .map_err(map_err)?;
+    }
+
+    Ok(())
+}
+
+fn delete_memo(conn: &Connection, account: &str, memo_name: &str) -> Result<(), String> {
+    for sql in [
+        "DELETE FROM memo WHERE account = ?1 AND name = ?2",
+        "DELETE FROM memo_resource WHERE account = ?1 AND memo_name = ?2",
+        "DELETE FROM memo_relation WHERE account = ?1 AND memo_name = ?2",
+        "DELETE FROM memo_term WHERE account = ?1 AND memo_name = ?2",
+    ] {
+        conn.execute(sql, params![account, memo_name])
+            .map_err(|e| format!("Failed to delete memo cache: {}", e))?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::models::V1State;
+
+    fn memo(name: &str, tags: &[&str]) -> Memo {
+        Memo {
+            name: name.to_string(),
+            content: name.to_string(),
+            tags: tags.iter().map(|x| x.to_string()).collect(),
+            state: V1State::Normal,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_migrate_from_json() {
+        let dir = tempfile::tempdir().unwrap();
+        let fs = FsMemoStore::new(dir.path().to_path_buf());
+        let meta = DashMap::new();
+        for x in [memo("memos/1", &["b", "a"]), memo("memos/2", &[])] {
+            meta.insert(x.name.clone(), MemoMeta::from(&x));
+            fs.save_memo("account", &x).unwrap();
+        }
+        fs.save_meta("account", &meta, &HashSet::new()).unwrap();
+        let outbox = dir.path().join("account").join("outbox.json");
+        std::fs::write(&outbox, "{}").unwrap();
+
+        let store = SqliteMemoStore::open_in_memory().unwrap();
+        store.migrate_from(&fs).unwrap();
+        assert!(fs.accounts().is_empty());
+        assert!(outbox.exists());
+
+        assert_eq!(store.load_meta("account").unwrap().len(), 2);
+        assert_eq!(
+            store
+                .load_meta("account")
+                .unwrap()
+                .get("memos/1")
+                .unwrap()
+                .tags,
+            ["b", "a"]
+        );
+        assert_eq!(
+            store.load_memo("account", "memos/1").unwrap(),
+            Some(memo("memos/1", &["b", "a"]))
+        );
+
+        let memos = store
+            .load_memos(
+                "account",
+                &[
+                    "memos/1".to_string(),
+                    "memos/3".to_string(),
+                    "memos/2".to_string(),
+                ],
+            )
+            .unwrap();
+        assert_eq!(memos.len(), 2);
+        assert_eq!(memos["memos/2"], memo("memos/2", &[]));
+
+        let postings = store
+            .load_postings("account", &["memos".to_string(), "2".to_string()])
+            .unwrap();
+        assert_eq!(postings.memo_count, 2);
+        assert_eq!(postings.terms["memos"].len(), 2);
+        assert_eq!(
+            postings.rank(&search::parse_query("memos 2"))[0].0,
+            "memos/2"
+        );
+        assert_eq!(store.load_terms("account", 5, 5).unwrap(), ["memos"]);
+
+        meta.remove("memos/2");
+        let changed = HashSet::from(["memos/2".to_string()]);
+        store.save_meta("account", &meta, &changed).unwrap();
+        assert_eq!(store.load_meta("account").unwrap().len(), 1);
+
+        // Removing a memo takes its metadata row along, like every store.
+        store.remove_memo("account", "memos/1").unwrap();
+        assert_eq!(store.load_memo("account", "memos/1").unwrap(), None);
+        assert!(store.load_meta("account").unwrap().is_empty());
+    }
+}
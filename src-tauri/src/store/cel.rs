@@ -0,0 +1,586 @@
+use std::fmt;
+
+use chrono::Utc;
+
+use crate::{
+    store::model::{Memo, MemoMeta},
+    utils::time::parse_timestamp,
+};
+
+/// The subset of the Memos filter language (CEL) the server accepts in
+/// `ListMemosRequest::filter`, e.g. `tag in ["work"] && !pinned`.
+///
+/// Times are Unix seconds like on the server, `timestamp("2024-01-01T00:00:00Z")`
+/// and `now()` give the same. Deserializes from the filter string and is
+/// checked against an empty memo there, so a bad filter fails up front.
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct FilterExpr {
+    source: String,
+    expr: Expr,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Value {
+    Bool(bool),
+    Int(i64),
+    Str(String),
+    List(Vec<Value>),
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Bool(_) => write!(f, "bool"),
+            Self::Int(_) => write!(f, "int"),
+            Self::Str(_) => write!(f, "string"),
+            Self::List(_) => write!(f, "list"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Op {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    In,
+    Add,
+    Sub,
+    Mul,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Expr {
+    Value(Value),
+    List(Vec<Expr>),
+    Field(String),
+    Call {
+        target: Option<Box<Expr>>,
+        name: String,
+        args: Vec<Expr>,
+    },
+    Not(Box<Expr>),
+    And(Box<Expr>, Box<Expr>),
+    Or(Box<Expr>, Box<Expr>),
+    Binary(Op, Box<Expr>, Box<Expr>),
+}
+
+/// Fields read from the memo content rather than its metadata.
+const MEMO_FIELDS: &[&str] = &[
+    "content",
+    "has_task_list",
+    "has_link",
+    "has_code",
+    "has_incomplete_tasks",
+];
+
+impl FilterExpr {
+    pub fn parse(source: &str) -> Result<Self, String> {
+        let tokens = tokenize(source)?;
+        let mut parser = Parser { tokens, pos: 0 };
+        let expr = parser.or()?;
+        if let Some(token) = parser.tokens.get(parser.pos) {
+            return Err(format!("Invalid filter: unexpected {:?}", token));
+        }
+
+        let filter = Self {
+            source: source.to_string(),
+            expr,
+        };
+        let meta = MemoMeta::default();
+        let memo = Memo::default();
+        match filter.eval(&meta, Some(&memo))? {
+            Value::Bool(_) => Ok(filter),
+            x => Err(format!("Invalid filter: {} is not a condition", x)),
+        }
+    }
+
+    /// Whether `matches_memo` needs more than the metadata.
+    pub fn needs_memo(&self) -> bool {
+        self.expr.needs_memo()
+    }
+
+    pub fn matches_meta(&self, meta: &MemoMeta) -> bool {
+        self.eval(meta, None) == Ok(Value::Bool(true))
+    }
+
+    pub fn matches_memo(&self, memo: &Memo) -> bool {
+        self.eval(&MemoMeta::from(memo), Some(memo)) == Ok(Value::Bool(true))
+    }
+
+    fn eval(&self, meta: &MemoMeta, memo: Option<&Memo>) -> Result<Value, String> {
+        Subject { meta, memo }
+            .eval(&self.expr)
+            .map_err(|e| format!("Invalid filter: {}", e))
+    }
+}
+
+impl TryFrom<String> for FilterExpr {
+    type Error = String;
+
+    fn try_from(source: String) -> Result<Self, Self::Error> {
+        Self::parse(&source)
+    }
+}
+
+impl From<FilterExpr> for String {
+    fn from(filter: FilterExpr) -> Self {
+        filter.source
+    }
+}
+
+impl Expr {
+    fn needs_memo(&self) -> bool {
+        match self {
+            Self::Value(_) => false,
+            Self::Field(name) => MEMO_FIELDS.contains(&name.as_str()),
+            Self::List(items) => items.iter().any(Self::needs_memo),
+            Self::Call { target, args, .. } => {
+                target.as_ref().is_some_and(|x| x.needs_memo()) || args.iter().any(Self::needs_memo)
+            }
+            Self::Not(x) => x.needs_memo(),
+            Self::And(a, b) | Self::Or(a, b) | Self::Binary(_, a, b) => {
+                a.needs_memo() || b.needs_memo()
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Ident(String),
+    Int(i64),
+    Str(String),
+    Punct(&'static str),
+}
+
+const PUNCTS: &[&str] = &[
+    "&&", "||", "==", "!=", "<=", ">=", "<", ">", "!", "(", ")", "[", "]", ",", ".", "+", "-", "*",
+];
+
+fn tokenize(source: &str) -> Result<Vec<Token>, String> {
+    let mut tokens = Vec::new();
+    let mut rest = source;
+    while let Some(c) = rest.chars().next() {
+        if c.is_whitespace() {
+            rest = &rest[c.len_utf8()..];
+        } else if c == '"' || c == '\'' {
+            let mut value = String::new();
+            let mut chars = rest[1..].char_indices();
+            let end = loop {
+                match chars.next() {
+                    Some((i, x)) if x == c => break i + 2,
+                    Some((_, '\\')) => match chars.next() {
+                        Some((_, 'n')) => value.push('\n'),
+                        Some((_, 't')) => value.push('\t'),
+                        Some((_, x)) => value.push(x),
+                        None => return Err("Invalid filter: unterminated string".to_string()),
+                    },
+                    Some((_, x)) => value.push(x),
+                    None => return Err("Invalid filter: unterminated string".to_string()),
+                }
+            };
+            tokens.push(Token::Str(value));
+            rest = &rest[end..];
+        } else if c.is_ascii_digit() {
+            let end = rest
+                .find(|x: char| !x.is_ascii_digit())
+                .unwrap_or(rest.len());
+            let value = rest[..end]
+                .parse()
+                .map_err(|e| format!("Invalid filter: {}", e))?;
+            tokens.push(Token::Int(value));
+            rest = &rest[end..];
+        } else if c.is_ascii_alphabetic() || c == '_' {
+            let end = rest
+                .find(|x: char| !x.is_ascii_alphanumeric() && x != '_')
+                .unwrap_or(rest.len());
+            tokens.push(Token::Ident(rest[..end].to_string()));
+            rest = &rest[end..];
+        } else if let Some(punct) = PUNCTS.iter().find(|x| rest.starts_with(**x)) {
+            tokens.push(Token::Punct(punct));
+            rest = &rest[punct.len()..];
+        } else {
+            return Err(format!("Invalid filter: unexpected {:?}", c));
+        }
+    }
+    Ok(tokens)
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn eat(&mut self, punct: &str) -> bool {
+        let found = matches!(self.peek(), Some(Token::Punct(x)) if *x == punct);
+        if found {
+            self.pos += 1;
+        }
+        found
+    }
+
+    fn expect(&mut self, punct: &str) -> Result<(), String> {
+        if self.eat(punct) {
+            Ok(())
+        } else {
+            Err(format!(
+                "Invalid filter: expected {:?}, found {:?}",
+                punct,
+                self.peek()
+            ))
+        }
+    }
+
+    fn or(&mut self) -> Result<Expr, String> {
+        let mut expr = self.and()?;
+        while self.eat("||") {
+            expr = Expr::Or(Box::new(expr), Box::new(self.and()?));
+        }
+        Ok(expr)
+    }
+
+    fn and(&mut self) -> Result<Expr, String> {
+        let mut expr = self.compare()?;
+        while self.eat("&&") {
+            expr = Expr::And(Box::new(expr), Box::new(self.compare()?));
+        }
+        Ok(expr)
+    }
+
+    fn compare(&mut self) -> Result<Expr, String> {
+        let expr = self.sum()?;
+        let op = match self.peek() {
+            Some(Token::Punct("==")) => Op::Eq,
+            Some(Token::Punct("!=")) => Op::Ne,
+            Some(Token::Punct("<")) => Op::Lt,
+            Some(Token::Punct("<=")) => Op::Le,
+            Some(Token::Punct(">")) => Op::Gt,
+            Some(Token::Punct(">=")) => Op::Ge,
+            Some(Token::Ident(x)) if x == "in" => Op::In,
+            _ => return Ok(expr),
+        };
+        self.pos += 1;
+        Ok(Expr::Binary(op, Box::new(expr), Box::new(self.sum()?)))
+    }
+
+    fn sum(&mut self) -> Result<Expr, String> {
+        let mut expr = self.product()?;
+        loop {
+            let op = if self.eat("+") {
+                Op::Add
+            } else if self.eat("-") {
+                Op::Sub
+            } else {
+                return Ok(expr);
+            };
+            expr = Expr::Binary(op, Box::new(expr), Box::new(self.product()?));
+        }
+    }
+
+    fn product(&mut self) -> Result<Expr, String> {
+        let mut expr = self.unary()?;
+        while self.eat("*") {
+            expr = Expr::Binary(Op::Mul, Box::new(expr), Box::new(self.unary()?));
+        }
+        Ok(expr)
+    }
+
+    /// `!` binds tighter than any binary operator, like in CEL.
+    fn unary(&mut self) -> Result<Expr, String> {
+        if self.eat("!") {
+            return Ok(Expr::Not(Box::new(self.unary()?)));
+        }
+        self.postfix()
+    }
+
+    fn postfix(&mut self) -> Result<Expr, String> {
+        let mut expr = self.primary()?;
+        while self.eat(".") {
+            let Some(Token::Ident(name)) = self.tokens.get(self.pos).cloned() else {
+                return Err("Invalid filter: expected a method after \".\"".to_string());
+            };
+            self.pos += 1;
+            self.expect("(")?;
+            expr = Expr::Call {
+                target: Some(Box::new(expr)),
+                name,
+                args: self.items(")")?,
+            };
+        }
+        Ok(expr)
+    }
+
+    /// Comma separated expressions up to `close`.
+    fn items(&mut self, close: &str) -> Result<Vec<Expr>, String> {
+        let mut items = Vec::new();
+        while !self.eat(close) {
+            if !items.is_empty() {
+                self.expect(",")?;
+                // A trailing comma like in `["a", "b",]`.
+                if self.eat(close) {
+                    break;
+                }
+            }
+            items.push(self.or()?);
+        }
+        Ok(items)
+    }
+
+    fn primary(&mut self) -> Result<Expr, String> {
+        let token = self
+            .tokens
+            .get(self.pos)
+            .cloned()
+            .ok_or_else(|| "Invalid filter: unexpected end".to_string())?;
+        self.pos += 1;
+
+        Ok(match token {
+            Token::Int(x) => Expr::Value(Value::Int(x)),
+            Token::Str(x) => Expr::Value(Value::Str(x)),
+            Token::Punct("-") => Expr::Binary(
+                Op::Sub,
+                Box::new(Expr::Value(Value::Int(0))),
+                Box::new(self.postfix()?),
+            ),
+            Token::Punct("(") => {
+                let expr = self.or()?;
+                self.expect(")")?;
+                expr
+            }
+            Token::Punct("[") => Expr::List(self.items("]")?),
+            Token::Ident(x) if x == "true" => Expr::Value(Value::Bool(true)),
+            Token::Ident(x) if x == "false" => Expr::Value(Value::Bool(false)),
+            Token::Ident(name) if self.eat("(") => Expr::Call {
+                target: None,
+                name,
+                args: self.items(")")?,
+            },
+            Token::Ident(name) => Expr::Field(name),
+            token => return Err(format!("Invalid filter: unexpected {:?}", token)),
+        })
+    }
+}
+
+struct Subject<'a> {
+    meta: &'a MemoMeta,
+    memo: Option<&'a Memo>,
+}
+
+fn seconds(time: &str) -> i64 {
+    parse_timestamp(time).map_or(0, |x| x.timestamp())
+}
+
+/// Like the server, `tag in ["a"]` also matches nested tags like `a/b`.
+fn is_tag(tag: &str, filter: &str) -> bool {
+    tag.strip_prefix(filter)
+        .is_some_and(|x| x.is_empty() || x.starts_with('/'))
+}
+
+impl Subject<'_> {
+    fn field(&self, name: &str) -> Result<Value, String> {
+        let meta = self.meta;
+        if MEMO_FIELDS.contains(&name) {
+            let memo = self.memo.ok_or("memo content not loaded")?;
+            if name == "content" {
+                return Ok(Value::Str(memo.content.clone()));
+            }
+            let property = memo.property();
+            return Ok(match name {
+                "has_task_list" => Value::Bool(property.has_task_list == Some(true)),
+                "has_link" => Value::Bool(property.has_link == Some(true)),
+                "has_code" => Value::Bool(property.has_code == Some(true)),
+                _ => Value::Bool(property.has_incomplete_tasks == Some(true)),
+            });
+        }
+
+        Ok(match name {
+            "tags" => Value::List(meta.tags.iter().cloned().map(Value::Str).collect()),
+            "visibility" => Value::Str(meta.visibility.clone()),
+            "pinned" => Value::Bool(meta.pinned),
+            "created_ts" | "create_time" => Value::Int(seconds(&meta.create_time)),
+            "updated_ts" | "update_time" => Value::Int(seconds(&meta.update_time)),
+            "display_time" => Value::Int(seconds(&meta.display_time)),
+            _ => return Err(format!("unknown field {:?}", name)),
+        })
+    }
+
+    fn eval(&self, expr: &Expr) -> Result<Value, String> {
+        match expr {
+            Expr::Value(x) => Ok(x.clone()),
+            Expr::List(items) => Ok(Value::List(
+                items
+                    .iter()
+                    .map(|x| self.eval(x))
+                    .collect::<Result<_, _>>()?,
+            )),
+            Expr::Field(name) => self.field(name),
+            Expr::Not(x) => Ok(Value::Bool(!self.eval_bool(x)?)),
+            // Both sides always, so `FilterExpr::parse` checks all of them.
+            Expr::And(a, b) => {
+                let (a, b) = (self.eval_bool(a)?, self.eval_bool(b)?);
+                Ok(Value::Bool(a && b))
+            }
+            Expr::Or(a, b) => {
+                let (a, b) = (self.eval_bool(a)?, self.eval_bool(b)?);
+                Ok(Value::Bool(a || b))
+            }
+            Expr::Binary(op, a, b) if **a == Expr::Field("tag".to_string()) => {
+                self.eval_tag(*op, b)
+            }
+            Expr::Binary(op, a, b) => binary(*op, self.eval(a)?, self.eval(b)?),
+            Expr::Call { target, name, args } => {
+                let target = target.as_ref().map(|x| self.eval(x)).transpose()?;
+                let args = args
+                    .iter()
+                    .map(|x| self.eval(x))
+                    .collect::<Result<Vec<_>, _>>()?;
+                call(target, name, &args)
+            }
+        }
+    }
+
+    fn eval_bool(&self, expr: &Expr) -> Result<bool, String> {
+        match self.eval(expr)? {
+            Value::Bool(x) => Ok(x),
+            x => Err(format!("expected bool, found {}", x)),
+        }
+    }
+
+    /// `tag` stands for any tag of the memo.
+    fn eval_tag(&self, op: Op, filter: &Expr) -> Result<Value, String> {
+        let filters = match (op, self.eval(filter)?) {
+            (Op::Eq, Value::Str(x)) => vec![x],
+            (Op::In, Value::List(items)) => items
+                .into_iter()
+                .map(|x| match x {
+                    Value::Str(x) => Ok(x),
+                    x => Err(format!("expected string tag, found {}", x)),
+                })
+                .collect::<Result<_, _>>()?,
+            _ => return Err("tag only supports `==` and `in`".to_string()),
+        };
+        Ok(Value::Bool(
+            self.meta
+                .tags
+                .iter()
+                .any(|tag| filters.iter().any(|x| is_tag(tag, x))),
+        ))
+    }
+}
+
+fn binary(op: Op, a: Value, b: Value) -> Result<Value, String> {
+    Ok(match (op, a, b) {
+        (Op::Eq, a, b) => Value::Bool(a == b),
+        (Op::Ne, a, b) => Value::Bool(a != b),
+        (Op::In, a, Value::List(items)) => Value::Bool(items.contains(&a)),
+        (Op::Add, Value::Int(a), Value::Int(b)) => Value::Int(a.saturating_add(b)),
+        (Op::Sub, Value::Int(a), Value::Int(b)) => Value::Int(a.saturating_sub(b)),
+        (Op::Mul, Value::Int(a), Value::Int(b)) => Value::Int(a.saturating_mul(b)),
+        (op, Value::Int(a), Value::Int(b)) => Value::Bool(match op {
+            Op::Lt => a < b,
+            Op::Le => a <= b,
+            Op::Gt => a > b,
+            _ => a >= b,
+        }),
+        (op, Value::Str(a), Value::Str(b))
+            if !matches!(op, Op::In | Op::Add | Op::Sub | Op::Mul) =>
+        {
+            Value::Bool(match op {
+                Op::Lt => a < b,
+                Op::Le => a <= b,
+                Op::Gt => a > b,
+                _ => a >= b,
+            })
+        }
+        (op, a, b) => return Err(format!("{:?} does not apply to {} and {}", op, a, b)),
+    })
+}
+
+fn call(target: Option<Value>, name: &str, args: &[Value]) -> Result<Value, String> {
+    Ok(match (target.as_ref(), name, args) {
+        (None, "now", []) => Value::Int(Utc::now().timestamp()),
+        (None, "timestamp", [Value::Str(x)]) => Value::Int(
+            parse_timestamp(x)
+                .ok_or_else(|| format!("invalid timestamp {:?}", x))?
+                .timestamp(),
+        ),
+        (None, "size", [Value::List(x)]) | (Some(Value::List(x)), "size", []) => {
+            Value::Int(x.len() as i64)
+        }
+        (Some(Value::Str(x)), "contains", [Value::Str(y)]) => Value::Bool(x.contains(y.as_str())),
+        (Some(Value::Str(x)), "startsWith", [Value::Str(y)]) => {
+            Value::Bool(x.starts_with(y.as_str()))
+        }
+        (Some(Value::Str(x)), "endsWith", [Value::Str(y)]) => Value::Bool(x.ends_with(y.as_str())),
+        _ => return Err(format!("unknown function {:?}", name)),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn memo(content: &str, tags: &[&str], pinned: bool) -> Memo {
+        Memo {
+            content: content.to_string(),
+            create_time: "2024-01-02T00:00:00Z".to_string(),
+            visibility: "PRIVATE".to_string(),
+            pinned,
+            tags: tags.iter().map(|x| x.to_string()).collect(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_filter_expr() {
+        let filter = FilterExpr::parse(r#"tag in ["work", "home"] && !pinned"#).unwrap();
+        assert!(!filter.needs_memo());
+        assert!(filter.matches_memo(&memo("", &["work/report"], false)));
+        assert!(!filter.matches_memo(&memo("", &["workshop"], false)));
+        assert!(!filter.matches_memo(&memo("", &["home"], true)));
+
+        let filter = FilterExpr::parse(
+            "content.contains('milk') || (visibility in [\"PUBLIC\"] && \
+            created_ts >= timestamp(\"2024-01-01T00:00:00Z\") + 60 * 60 * 24)",
+        )
+        .unwrap();
+        assert!(filter.needs_memo());
+        assert!(filter.matches_memo(&memo("buy milk", &[], false)));
+        assert!(!filter.matches_memo(&memo("", &[], false)));
+        let mut public = memo("", &[], false);
+        public.visibility = "PUBLIC".to_string();
+        assert!(filter.matches_memo(&public));
+
+        let filter = FilterExpr::parse("has_task_list && created_ts < now()").unwrap();
+        assert!(filter.matches_memo(&memo("- [ ] todo", &[], false)));
+
+        for bad in [
+            "pinned ==",
+            "unknown == 1",
+            "visibility",
+            "pinned && 1",
+            "tag > \"a\"",
+            "'open",
+        ] {
+            assert!(FilterExpr::parse(bad).is_err(), "{}", bad);
+        }
+    }
+
+    #[test]
+    fn test_not_precedence() {
+        let parse = |source: &str| FilterExpr::parse(source).unwrap().expr;
+        assert_eq!(parse("!pinned == true"), parse("(!pinned) == true"));
+        assert_ne!(parse("!pinned == true"), parse("!(pinned == true)"));
+        assert!(FilterExpr::parse("!visibility == \"PUBLIC\"").is_err());
+    }
+}
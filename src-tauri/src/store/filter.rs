@@ -0,0 +1,209 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    api::models::V1State,
+    store::{
+        cel::FilterExpr,
+        model::{Memo, MemoMeta},
+    },
+    utils::time::parse_timestamp,
+};
+
+/// A tag condition, as JSON `{"tag": "a"}`, `{"and": [..]}`, `{"or": [..]}`
+/// or `{"not": ..}`. Tags match exactly.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum TagExpr {
+    Tag(String),
+    And(Vec<TagExpr>),
+    Or(Vec<TagExpr>),
+    Not(Box<TagExpr>),
+}
+
+impl TagExpr {
+    pub fn matches(&self, tags: &[String]) -> bool {
+        match self {
+            Self::Tag(tag) => tags.contains(tag),
+            Self::And(exprs) => exprs.iter().all(|x| x.matches(tags)),
+            Self::Or(exprs) => exprs.iter().any(|x| x.matches(tags)),
+            Self::Not(expr) => !expr.matches(tags),
+        }
+    }
+
+    /// A tag every matching memo has, so only its list needs a scan.
+    pub fn required_tag(&self) -> Option<&str> {
+        match self {
+            Self::Tag(tag) => Some(tag),
+            Self::And(exprs) => exprs.iter().find_map(|x| x.required_tag()),
+            Self::Or(_) | Self::Not(_) => None,
+        }
+    }
+}
+
+/// From `after` on and before `before`, open ended where `None`.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TimeRange {
+    pub after: Option<String>,
+    pub before: Option<String>,
+}
+
+impl TimeRange {
+    fn is_unbounded(&self) -> bool {
+        self.after.is_none() && self.before.is_none()
+    }
+
+    /// Unparsable times are only in an unbounded range.
+    pub fn contains(&self, time: &str) -> bool {
+        if self.is_unbounded() {
+            return true;
+        }
+        let Some(time) = parse_timestamp(time) else {
+            return false;
+        };
+        let after = self.after.as_deref().and_then(parse_timestamp);
+        let before = self.before.as_deref().and_then(parse_timestamp);
+        after.is_none_or(|x| time >= x) && before.is_none_or(|x| time < x)
+    }
+}
+
+/// Conditions on cached memos, all of which have to hold. Unset fields
+/// match everything.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct MemoFilter {
+    pub tags: Option<TagExpr>,
+    /// Any of these, e.g. `PRIVATE`.
+    pub visibilities: Vec<String>,
+    pub archived: bool,
+    pub pinned: Option<bool>,
+    pub create_time: TimeRange,
+    pub display_time: TimeRange,
+    pub has_resources: Option<bool>,
+    pub has_links: Option<bool>,
+    pub has_tasks: Option<bool>,
+    /// Case insensitive substring of the content.
+    pub content: Option<String>,
+    /// A Memos filter string like `tag in ["a"] && pinned`, see `FilterExpr`.
+    pub expression: Option<FilterExpr>,
+}
+
+impl MemoFilter {
+    pub fn state(&self) -> V1State {
+        if self.archived {
+            V1State::Archived
+        } else {
+            V1State::Normal
+        }
+    }
+
+    pub fn matches_meta(&self, meta: &MemoMeta) -> bool {
+        meta.state == self.state()
+            && self.tags.as_ref().is_none_or(|x| x.matches(&meta.tags))
+            && (self.visibilities.is_empty()
+                || self
+                    .visibilities
+                    .iter()
+                    .any(|x| x.eq_ignore_ascii_case(&meta.visibility)))
+            && self.pinned.is_none_or(|x| x == meta.pinned)
+            && self.create_time.contains(&meta.create_time)
+            && self.display_time.contains(&meta.display_time)
+            && self
+                .expression
+                .as_ref()
+                .is_none_or(|x| x.needs_memo() || x.matches_meta(meta))
+    }
+
+    /// Whether `matches_memo` needs more than the metadata.
+    pub fn needs_memo(&self) -> bool {
+        self.has_resources.is_some()
+            || self.has_links.is_some()
+            || self.has_tasks.is_some()
+            || self.content.as_ref().is_some_and(|x| !x.is_empty())
+            || self.expression.as_ref().is_some_and(|x| x.needs_memo())
+    }
+
+    pub fn matches_memo(&self, memo: &Memo) -> bool {
+        if self.has_links.is_some() || self.has_tasks.is_some() {
+            let property = memo.property();
+            if self.has_links.is_some_and(|x| Some(x) != property.has_link)
+                || self
+                    .has_tasks
+                    .is_some_and(|x| Some(x) != property.has_task_list)
+            {
+                return false;
+            }
+        }
+        self.matches_meta(&MemoMeta::from(memo))
+            && self
+                .has_resources
+                .is_none_or(|x| x != memo.resources.is_empty())
+            && self
+                .content
+                .as_ref()
+                .is_none_or(|x| memo.content.to_lowercase().contains(&x.to_lowercase()))
+            && self
+                .expression
+                .as_ref()
+                .is_none_or(|x| x.matches_memo(memo))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn memo(content: &str, tags: &[&str]) -> Memo {
+        Memo {
+            content: content.to_string(),
+            display_time: "2024-01-02T00:00:00Z".to_string(),
+            create_time: "2024-01-02T00:00:00Z".to_string(),
+            visibility: "PRIVATE".to_string(),
+            tags: tags.iter().map(|x| x.to_string()).collect(),
+            state: V1State::Normal,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_matches_memo() {
+        let tags = serde_json::from_str::<TagExpr>(
+            r#"{"and": [{"or": [{"tag": "a"}, {"tag": "b"}]}, {"not": {"tag": "c"}}]}"#,
+        )
+        .unwrap();
+        assert_eq!(tags.required_tag(), None);
+        let filter = MemoFilter {
+            tags: Some(tags),
+            ..Default::default()
+        };
+        assert!(filter.matches_memo(&memo("", &["b"])));
+        assert!(!filter.matches_memo(&memo("", &["a", "c"])));
+        assert!(!filter.matches_memo(&memo("", &[])));
+
+        let filter = MemoFilter {
+            visibilities: vec!["public".to_string()],
+            ..Default::default()
+        };
+        assert!(!filter.matches_memo(&memo("", &[])));
+
+        let filter = MemoFilter {
+            display_time: TimeRange {
+                after: Some("2024-01-01T00:00:00+08:00".to_string()),
+                before: Some("2024-01-02T00:00:00Z".to_string()),
+            },
+            ..Default::default()
+        };
+        assert!(!filter.matches_memo(&memo("", &[])));
+
+        let filter = MemoFilter {
+            has_tasks: Some(true),
+            has_links: Some(false),
+            content: Some("MILK".to_string()),
+            ..Default::default()
+        };
+        assert!(filter.needs_memo());
+        assert!(filter.matches_memo(&memo("- [ ] buy milk", &[])));
+        assert!(!filter.matches_memo(&memo("- [x] buy milk at https://x.y", &[])));
+        assert!(!filter.matches_memo(&memo("-  buy milk", &[])));
+    }
+}
@@ -0,0 +1,282 @@
+use std::{
+    collections::{BTreeSet, HashMap},
+    ops::Bound,
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    api::models::{V1Direction, V1State},
+    store::model::MemoMeta,
+};
+
+/// Memo list sort keys, named like the server's `sort`/`orderBy` fields.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum SortField {
+    #[default]
+    #[serde(rename = "display_time")]
+    Display,
+    #[serde(rename = "create_time")]
+    Create,
+    #[serde(rename = "update_time")]
+    Update,
+}
+
+impl SortField {
+    const ALL: [SortField; 3] = [Self::Display, Self::Create, Self::Update];
+
+    fn key(self, meta: &MemoMeta) -> SortKey {
+        let time = match self {
+            Self::Display => &meta.display_time,
+            Self::Create => &meta.create_time,
+            Self::Update => &meta.update_time,
+        };
+        (time.clone(), meta.name.clone())
+    }
+}
+
+/// Like the server, an unspecified direction is newest first.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MemoSort {
+    #[serde(default)]
+    pub field: SortField,
+    #[serde(default)]
+    pub direction: V1Direction,
+}
+
+impl MemoSort {
+    fn is_asc(&self) -> bool {
+        self.direction == V1Direction::Asc
+    }
+}
+
+/// The sort field's time, then the name for a total order.
+pub type SortKey = (String, String);
+
+/// One list of `get_memo_list`, an empty `tag` is all memos and a `None`
+/// `pinned` both pinned and other memos.
+#[derive(Debug, Clone, Copy)]
+pub struct ListFilter<'a> {
+    pub tag: &'a str,
+    pub pinned: Option<bool>,
+    pub state: V1State,
+}
+
+#[derive(Debug, PartialEq, Eq, Hash)]
+struct ListKey {
+    field: SortField,
+    tag: Option<String>,
+    pinned: bool,
+    state: V1State,
+}
+
+/// Sorted memo lists of `get_memo_list`, kept in step with `all_memo_meta`
+/// by `CacheData`. Every list is kept once per `SortField`.
+#[derive(Debug, Default)]
+pub struct MemoIndex {
+    lists: HashMap<ListKey, BTreeSet<SortKey>>,
+}
+
+impl MemoIndex {
+    fn list_keys(meta: &MemoMeta) -> impl Iterator<Item = ListKey> + '_ {
+        let tags = std::iter::once(None).chain(meta.tags.iter().map(Some));
+        tags.flat_map(move |tag| {
+            SortField::ALL.into_iter().map(move |field| ListKey {
+                field,
+                tag: tag.cloned(),
+                pinned: meta.pinned,
+                state: meta.state,
+            })
+        })
+    }
+
+    pub fn insert(&mut self, meta: &MemoMeta) {
+        for list_key in Self::list_keys(meta) {
+            let key = list_key.field.key(meta);
+            self.lists.entry(list_key).or_default().insert(key);
+        }
+    }
+
+    pub fn remove(&mut self, meta: &MemoMeta) {
+        for list_key in Self::list_keys(meta) {
+            let key = list_key.field.key(meta);
+            if let Some(list) = self.lists.get_mut(&list_key) {
+                list.remove(&key);
+                if list.is_empty() {
+                    self.lists.remove(&list_key);
+                }
+            }
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.lists.clear();
+    }
+
+    /// Keys of one page of a list, starting after the `after` key if given
+    /// and then skipping `offset` memos.
+    pub fn page(
+        &self,
+        filter: ListFilter,
+        sort: MemoSort,
+        after: Option<&SortKey>,
+        offset: usize,
+        limit: usize,
+    ) -> Vec<SortKey> {
+        self.scan(filter, sort, after)
+            .skip(offset)
+            .take(limit)
+            .cloned()
+            .collect()
+    }
+
+    /// Keys of a list in `sort` order, starting after the `after` key.
+    pub fn scan<'a>(
+        &'a self,
+        filter: ListFilter,
+        sort: MemoSort,
+        after: Option<&SortKey>,
+    ) -> Box<dyn Iterator<Item = &'a SortKey> + 'a> {
+        let Some(pinned) = filter.pinned else {
+            let pinned = self.scan(
+                ListFilter {
+                    pinned: Some(true),
+                    ..filter
+                },
+                sort,
+                after,
+            );
+            let others = self.scan(
+                ListFilter {
+                    pinned: Some(false),
+                    ..filter
+                },
+                sort,
+                after,
+            );
+            return Box::new(merge(pinned, others, sort.is_asc()));
+        };
+
+        let list_key = ListKey {
+            field: sort.field,
+            tag: (!filter.tag.is_empty()).then(|| filter.tag.to_string()),
+            pinned,
+            state: filter.state,
+        };
+        let Some(list) = self.lists.get(&list_key) else {
+            return Box::new(std::iter::empty());
+        };
+
+        match (sort.is_asc(), after) {
+            (true, Some(after)) => Box::new(list.range((Bound::Excluded(after), Bound::Unbounded))),
+            (true, None) => Box::new(list.iter()),
+            (false, Some(after)) => {
+                Box::new(list.range((Bound::Unbounded, Bound::Excluded(after))).rev())
+            }
+            (false, None) => Box::new(list.iter().rev()),
+        }
+    }
+}
+
+/// Merges two sorted key iterators into one.
+fn merge<'a>(
+    a: impl Iterator<Item = &'a SortKey>,
+    b: impl Iterator<Item = &'a SortKey>,
+    asc: bool,
+) -> impl Iterator<Item = &'a SortKey> {
+    let (mut a, mut b) = (a.peekable(), b.peekable());
+    std::iter::from_fn(move || {
+        let take_a = match (a.peek(), b.peek()) {
+            (Some(x), Some(y)) => (x < y) == asc,
+            (Some(_), None) => true,
+            (None, _) => false,
+        };
+        if take_a { a.next() } else { b.next() }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn meta(name: &str, display_time: &str, tags: &[&str]) -> MemoMeta {
+        MemoMeta {
+            name: name.to_string(),
+            display_time: display_time.to_string(),
+            create_time: name.to_string(),
+            tags: tags.iter().map(|x| x.to_string()).collect(),
+            state: V1State::Normal,
+            ..Default::default()
+        }
+    }
+
+    fn names(keys: Vec<SortKey>) -> Vec<String> {
+        keys.into_iter().map(|(_, name)| name).collect()
+    }
+
+    #[test]
+    fn test_page() {
+        let mut index = MemoIndex::default();
+        index.insert(&meta("memos/1", "2024-01-01T00:00:00Z", &["a"]));
+        index.insert(&meta("memos/2", "2024-01-03T00:00:00Z", &["a", "b"]));
+        index.insert(&meta("memos/3", "2024-01-02T00:00:00Z", &[]));
+
+        let all = ListFilter {
+            tag: "",
+            pinned: Some(false),
+            state: V1State::Normal,
+        };
+        let tag_a = ListFilter { tag: "a", ..all };
+        let newest = MemoSort::default();
+        assert_eq!(
+            names(index.page(all, newest, None, 0, 10)),
+            ["memos/2", "memos/3", "memos/1"]
+        );
+        assert_eq!(names(index.page(all, newest, None, 1, 1)), ["memos/3"]);
+        assert_eq!(
+            names(index.page(tag_a, newest, None, 0, 10)),
+            ["memos/2", "memos/1"]
+        );
+
+        let oldest_created = MemoSort {
+            field: SortField::Create,
+            direction: V1Direction::Asc,
+        };
+        let first = index.page(all, oldest_created, None, 0, 1);
+        assert_eq!(names(first.clone()), ["memos/1"]);
+        // A memo added before the cursor doesn't shift the next page.
+        index.insert(&meta("memos/0", "2024-01-04T00:00:00Z", &[]));
+        assert_eq!(
+            names(index.page(all, oldest_created, first.last(), 0, 10)),
+            ["memos/2", "memos/3"]
+        );
+
+        index.remove(&meta("memos/2", "2024-01-03T00:00:00Z", &["a", "b"]));
+        let mut pinned = meta("memos/2", "2024-01-03T00:00:00Z", &["a"]);
+        pinned.pinned = true;
+        index.insert(&pinned);
+        assert_eq!(names(index.page(tag_a, newest, None, 0, 10)), ["memos/1"]);
+        let pinned_a = ListFilter {
+            pinned: Some(true),
+            ..tag_a
+        };
+        assert_eq!(
+            names(index.page(pinned_a, newest, None, 0, 10)),
+            ["memos/2"]
+        );
+        let any_a = ListFilter {
+            pinned: None,
+            ..tag_a
+        };
+        assert_eq!(
+            names(index.page(any_a, newest, None, 0, 10)),
+            ["memos/2", "memos/1"]
+        );
+        assert!(
+            index
+                .page(ListFilter { tag: "b", ..all }, newest, None, 0, 10)
+                .is_empty()
+        );
+    }
+}
@@ -0,0 +1,266 @@
+use std::collections::{BTreeMap, HashMap};
+
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+
+use crate::store::model::Memo;
+
+/// Bounds of the in-memory memo cache, `0` disables a bound.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct CacheLimits {
+    pub max_entries: usize,
+    pub max_bytes: usize,
+}
+
+impl Default for CacheLimits {
+    fn default() -> Self {
+        Self {
+            max_entries: 1000,
+            max_bytes: 16 * 1024 * 1024,
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Entry {
+    memo: Memo,
+    size: usize,
+    /// Last use, the key in `Inner::order` once clean.
+    tick: u64,
+    /// Tick of the last change not written to the memo store yet.
+    dirty: Option<u64>,
+}
+
+#[derive(Debug, Default)]
+struct Inner {
+    entries: HashMap<String, Entry>,
+    /// Clean memos, least recently used first. Dirty ones can't be evicted
+    /// and join once written.
+    order: BTreeMap<u64, String>,
+    tick: u64,
+    bytes: usize,
+    limits: CacheLimits,
+}
+
+/// Memos in front of the memo store, evicting the least recently used ones
+/// over `CacheLimits`. Dirty memos stay until `mark_clean`.
+#[derive(Debug, Default)]
+pub struct MemoLru {
+    inner: Mutex<Inner>,
+}
+
+/// Rough heap size of a memo, enough to bound memory use.
+fn approx_size(memo: &Memo) -> usize {
+    256 + memo.name.len()
+        + memo.content.len()
+        + memo.tags.iter().map(|x| x.len() + 24).sum::<usize>()
+        + memo.resources.len() * 256
+        + memo.relations.len() * 128
+        + memo.reactions.len() * 96
+}
+
+impl Inner {
+    fn next_tick(&mut self) -> u64 {
+        self.tick += 1;
+        self.tick
+    }
+
+    fn touch(&mut self, memo_name: &str) {
+        let tick = self.next_tick();
+        if let Some(entry) = self.entries.get_mut(memo_name) {
+            if self.order.remove(&entry.tick).is_some() {
+                self.order.insert(tick, memo_name.to_string());
+            }
+            entry.tick = tick;
+        }
+    }
+
+    fn remove(&mut self, memo_name: &str) -> Option<Entry> {
+        let entry = self.entries.remove(memo_name)?;
+        self.order.remove(&entry.tick);
+        self.bytes -= entry.size;
+        Some(entry)
+    }
+
+    fn is_over(&self) -> bool {
+        let limits = self.limits;
+        (limits.max_entries > 0 && self.entries.len() > limits.max_entries)
+            || (limits.max_bytes > 0 && self.bytes > limits.max_bytes)
+    }
+
+    fn evict(&mut self) {
+        while self.is_over() {
+            let Some((_, victim)) = self.order.pop_first() else {
+                // Only dirty memos left, they go once written.
+                return;
+            };
+            self.remove(&victim);
+        }
+    }
+}
+
+impl MemoLru {
+    pub fn new(limits: CacheLimits) -> Self {
+        Self {
+            inner: Mutex::new(Inner {
+                limits,
+                ..Default::default()
+            }),
+        }
+    }
+
+    pub fn set_limits(&self, limits: CacheLimits) {
+        let mut inner = self.inner.lock();
+        inner.limits = limits;
+        inner.evict();
+    }
+
+    pub fn get(&self, memo_name: &str) -> Option<Memo> {
+        let mut inner = self.inner.lock();
+        inner.touch(memo_name);
+        inner.entries.get(memo_name).map(|x| x.memo.clone())
+    }
+
+    /// Like `get` without counting as a use, for scans over many memos.
+    pub fn peek(&self, memo_name: &str) -> Option<Memo> {
+        self.inner
+            .lock()
+            .entries
+            .get(memo_name)
+            .map(|x| x.memo.clone())
+    }
+
+    /// Whether the cached memo equals `memo`, without counting as a use.
+    pub fn is_same(&self, memo_name: &str, memo: &Memo) -> bool {
+        self.inner
+            .lock()
+            .entries
+            .get(memo_name)
+            .is_some_and(|x| x.memo == *memo)
+    }
+
+    /// `dirty` marks a change that `flush_memo_cache` has to write, a clean
+    /// insert keeps a pending change of the memo.
+    pub fn insert(&self, memo_name: String, memo: Memo, dirty: bool) {
+        let mut inner = self.inner.lock();
+        let tick = inner.next_tick();
+        let old_dirty = inner.remove(&memo_name).and_then(|x| x.dirty);
+
+        let size = approx_size(&memo);
+        let dirty = if dirty { Some(tick) } else { old_dirty };
+        inner.bytes += size;
+        if dirty.is_none() {
+            inner.order.insert(tick, memo_name.clone());
+        }
+        inner.entries.insert(
+            memo_name,
+            Entry {
+                memo,
+                size,
+                tick,
+                dirty,
+            },
+        );
+        inner.evict();
+    }
+
+    pub fn remove(&self, memo_name: &str) {
+        self.inner.lock().remove(memo_name);
+    }
+
+    pub fn clear(&self) {
+        let mut inner = self.inner.lock();
+        *inner = Inner {
+            limits: inner.limits,
+            ..Default::default()
+        };
+    }
+
+    #[cfg(test)]
+    fn len(&self) -> usize {
+        self.inner.lock().entries.len()
+    }
+
+    /// Memos with unwritten changes, with the change to pass to `mark_clean`.
+    pub fn dirty(&self) -> Vec<(String, Memo, u64)> {
+        self.inner
+            .lock()
+            .entries
+            .iter()
+            .filter_map(|(k, v)| Some((k.clone(), v.memo.clone(), v.dirty?)))
+            .collect()
+    }
+
+    /// Marks a written change, unless the memo changed again meanwhile.
+    pub fn mark_clean(&self, memo_name: &str, change: u64) {
+        let mut inner = self.inner.lock();
+        if let Some(entry) = inner.entries.get_mut(memo_name)
+            && entry.dirty == Some(change)
+        {
+            entry.dirty = None;
+            let tick = entry.tick;
+            inner.order.insert(tick, memo_name.to_string());
+            inner.evict();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn memo(name: &str) -> Memo {
+        Memo {
+            name: name.to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_evict_least_recently_used() {
+        let lru = MemoLru::new(CacheLimits {
+            max_entries: 2,
+            max_bytes: 0,
+        });
+
+        lru.insert("1".to_string(), memo("1"), false);
+        lru.insert("2".to_string(), memo("2"), true);
+        lru.get("1");
+        lru.insert("3".to_string(), memo("3"), false);
+        // 2 is the oldest but dirty.
+        assert_eq!(lru.len(), 2);
+        assert!(lru.get("2").is_some());
+        assert!(lru.get("1").is_none());
+
+        let dirty = lru.dirty();
+        assert_eq!(dirty.len(), 1);
+        lru.insert("2".to_string(), memo("2"), true);
+        lru.mark_clean("2", dirty[0].2);
+        assert_eq!(lru.dirty().len(), 1);
+
+        let change = lru.dirty()[0].2;
+        lru.get("3");
+        lru.mark_clean("2", change);
+        assert!(lru.dirty().is_empty());
+        lru.insert("4".to_string(), memo("4"), false);
+        assert!(lru.get("2").is_none());
+        assert!(lru.get("3").is_some());
+    }
+
+    #[test]
+    fn test_evict_by_size() {
+        let lru = MemoLru::new(CacheLimits {
+            max_entries: 0,
+            max_bytes: 2000,
+        });
+
+        for i in 0..10 {
+            let mut m = memo(&i.to_string());
+            m.content = "x".repeat(500);
+            lru.insert(i.to_string(), m, false);
+        }
+        assert_eq!(lru.len(), 2);
+        assert!(lru.get("9").is_some());
+    }
+}
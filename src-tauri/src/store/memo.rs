@@ -1,163 +1,127 @@
 use std::{
+    collections::HashMap,
     path::{Path, PathBuf},
-    sync::{
-        Arc,
-        atomic::{AtomicBool, Ordering},
-    },
+    time::Duration,
 };
 
 use crate::{
     api::models::V1State,
-    store::model::{AppState, CacheData, Memo, MemoDataCache, MemoMeta},
+    store::{
+        backend::MemoStore,
+        filter::MemoFilter,
+        index::{ListFilter, MemoSort, SortKey},
+        lru::CacheLimits,
+        model::{AppState, CacheData, MEMO_CACHE_LIMITS_KEY, Memo, MemoMeta},
+        save_store_data, with_state_blocking,
+    },
     utils::path::sanitize_file_name,
 };
-use dashmap::DashMap;
-use parking_lot::RwLock;
+use base64::{Engine, engine::general_purpose::URL_SAFE_NO_PAD};
+use serde::{Deserialize, Serialize};
 use tauri::{AppHandle, Manager, State};
+use tokio::time::Instant;
 
 #[tauri::command]
 pub async fn store_memo(
     state: State<'_, AppState>,
     memo_name: String,
-    memo: Memo,
+    mut memo: Memo,
 ) -> Result<(), String> {
     log::trace!("[store_memo] memo: {}", memo_name);
 
-    let memo_meta = MemoMeta {
-        name: memo_name.clone(),
-        create_time: memo.create_time.clone(),
-        update_time: memo.update_time.clone(),
-        display_time: memo.display_time.clone(),
-        visibility: memo.visibility.clone(),
-        pinned: memo.pinned,
-        tags: memo.tags.clone(),
-        state: memo.state,
-    };
-
-    let should_update = match state.cache.all_memo_meta.get(&memo_name) {
-        Some(existing) => existing.value() != &memo_meta,
-        None => true,
-    };
+    // Keep local edits until the outbox has pushed them.
+    if state.outbox().has_pending(&memo_name) {
+        return Ok(());
+    }
 
-    if should_update {
-        state
-            .cache
-            .all_memo_meta
-            .insert(memo_name.clone(), memo_meta);
-        state
-            .cache
-            .is_all_memo_meta_updated
-            .store(true, Ordering::Relaxed);
+    // Memos saved offline have no tags or property from the server yet.
+    if memo.property.is_none() {
+        memo.analyse_content();
     }
+    cache_memo(&state, memo_name, memo);
 
-    let should_update = match state.cache.memos.get(&memo_name) {
-        Some(existing) => existing.value().memo != memo,
-        None => true,
-    };
+    Ok(())
+}
 
-    if should_update {
-        state.cache.memos.insert(
-            memo_name,
-            MemoDataCache {
-                memo,
-                is_updated: AtomicBool::new(true),
-            },
-        );
+/// Puts a memo into the in-memory cache and marks the changed parts for
+/// `persist_memo_cache`. Returns whether anything changed.
+pub fn cache_memo(state: &AppState, memo_name: String, memo: Memo) -> bool {
+    let cache = state.cache();
+    let memo_meta = MemoMeta::from(&memo);
+
+    let meta_updated = cache.put_meta(memo_name.clone(), memo_meta);
+
+    let memo_updated = !cache.memos.is_same(&memo_name, &memo);
+    if memo_updated {
+        cache.memos.insert(memo_name, memo, true);
     }
 
-    Ok(())
+    if meta_updated || memo_updated {
+        state.cache_writer.notify();
+    }
+    meta_updated || memo_updated
 }
 
 #[tauri::command]
-pub async fn get_memo(
-    app: AppHandle,
-    state: State<'_, AppState>,
-    memo_name: String,
-) -> Result<Option<Memo>, String> {
+pub async fn get_memo(app: AppHandle, memo_name: String) -> Result<Option<Memo>, String> {
     log::trace!("[get_memo] memo: {}", memo_name);
 
-    if let Some(data) = state.cache.memos.get(&memo_name) {
-        return Ok(Some(data.memo.clone()));
-    }
+    with_state_blocking(&app, move |state| load_memo(state, &memo_name)).await
+}
 
-    let path = get_memo_cache_path(
-        &app.path()
-            .app_cache_dir()
-            .map_err(|e| format!("Failed to get app cache dir: {}", e))?,
-        &state.store.server_url.read(),
-        &state.store.user_name.read(),
-    )
-    .map_err(|e| format!("Failed to get memo cache path: {}", e))?
-    .join("memos")
-    .join("memo_".to_string() + &sanitize_file_name(&memo_name) + ".json");
-
-    if !path.exists() {
-        return Ok(None);
+/// Returns a cached memo, reading it from the memo store if it is not in
+/// memory yet.
+pub fn load_memo(state: &AppState, memo_name: &str) -> Result<Option<Memo>, String> {
+    let cache = state.cache();
+    if let Some(memo) = cache.memos.get(memo_name) {
+        return Ok(Some(memo));
     }
 
-    let content = tokio::fs::read_to_string(&path)
-        .await
-        .map_err(|e| format!("Failed to read memo cache: {}", e))?;
+    let Some(memo) = state.memo_store.load_memo(&state.account()?, memo_name)? else {
+        return Ok(None);
+    };
 
-    let memo: Memo =
-        serde_json::from_str(&content).map_err(|e| format!("Failed to parse memo cache: {}", e))?;
-
-    state.cache.memos.insert(
-        memo_name,
-        MemoDataCache {
-            memo: memo.clone(),
-            is_updated: AtomicBool::new(false),
-        },
-    );
+    cache
+        .memos
+        .insert(memo_name.to_string(), memo.clone(), false);
 
     Ok(Some(memo))
 }
 
 #[tauri::command]
-pub async fn delete_memo(
-    app: AppHandle,
-    state: State<'_, AppState>,
-    memo_name: String,
-) -> Result<(), String> {
+pub async fn delete_memo(app: AppHandle, memo_name: String) -> Result<(), String> {
     log::info!("[delete_memo] memo: {:?}", memo_name);
-    state.cache.all_memo_meta.remove(&memo_name);
-    state
-        .cache
-        .is_all_memo_meta_updated
-        .store(true, Ordering::Relaxed);
-
-    state.cache.memos.remove(&memo_name);
-
-    let path = get_memo_cache_path(
-        &app.path()
-            .app_cache_dir()
-            .map_err(|e| format!("Failed to get app cache dir: {}", e))?,
-        &state.store.server_url.read(),
-        &state.store.user_name.read(),
-    )
-    .map_err(|e| format!("Failed to get memo cache path: {}", e))?
-    .join("memos")
-    .join("memo_".to_string() + &sanitize_file_name(&memo_name) + ".json");
-
-    tokio::fs::remove_file(&path)
-        .await
-        .map_err(|e| {
-            log::error!(
-                "Failed to delete memo cache, memo: {}, path: {}, error: {}",
-                memo_name,
-                path.display(),
-                e
-            );
-        })
-        .ok();
 
-    Ok(())
+    with_state_blocking(&app, move |state| {
+        uncache_memo(state, &memo_name);
+        Ok(())
+    })
+    .await
+}
+
+/// Drops a memo from the in-memory cache and the memo store.
+pub fn uncache_memo(state: &AppState, memo_name: &str) {
+    let cache = state.cache();
+    cache.remove_meta(memo_name);
+
+    cache.memos.remove(memo_name);
+    state.cache_writer.notify();
+
+    if let Err(e) = state
+        .account()
+        .and_then(|account| state.memo_store.remove_memo(&account, memo_name))
+    {
+        log::error!(
+            "Failed to delete memo cache, memo: {}, error: {}",
+            memo_name,
+            e
+        );
+    }
 }
 
 #[tauri::command]
 pub async fn get_memo_list(
     app: AppHandle,
-    state: State<'_, AppState>,
     offset: usize,
     limit: usize,
     tag: String,
@@ -172,50 +136,358 @@ pub async fn get_memo_list(
         pinned,
         archived
     );
-    let mut memo_list = state
-        .cache
-        .all_memo_meta
-        .iter()
-        .filter(|x| x.pinned == pinned)
-        .filter(|x| {
-            if !tag.is_empty() {
-                x.tags.contains(&tag)
-            } else {
-                true
+
+    with_state_blocking(&app, move |state| {
+        list_memos(state, offset, limit, &tag, pinned, archived)
+    })
+    .await
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MemoPageRequest {
+    #[serde(default)]
+    pub tag: String,
+    #[serde(default)]
+    pub pinned: bool,
+    #[serde(default)]
+    pub archived: bool,
+    pub limit: usize,
+    #[serde(default)]
+    pub sort: MemoSort,
+    /// `next_cursor` of the previous page, `None` for the first one.
+    #[serde(default)]
+    pub cursor: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MemoPage {
+    pub memos: Vec<Memo>,
+    /// `None` on the last page.
+    pub next_cursor: Option<String>,
+}
+
+/// Position after the last memo of a page. Unlike an offset it stays put
+/// when memos are added or removed in front of it.
+#[derive(Debug, Serialize, Deserialize)]
+struct Cursor {
+    sort: MemoSort,
+    key: SortKey,
+    /// Pinned state of the list `key` is in, for `pinned_first` pages.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pinned: Option<bool>,
+}
+
+fn encode_cursor(cursor: &Cursor) -> Result<String, String> {
+    let json =
+        serde_json::to_vec(cursor).map_err(|e| format!("Failed to serialize cursor: {}", e))?;
+    Ok(URL_SAFE_NO_PAD.encode(json))
+}
+
+fn decode_cursor(cursor: &str) -> Result<Cursor, String> {
+    let json = URL_SAFE_NO_PAD
+        .decode(cursor)
+        .map_err(|e| format!("Invalid cursor: {}", e))?;
+    serde_json::from_slice(&json).map_err(|e| format!("Invalid cursor: {}", e))
+}
+
+/// Pages through a memo list with a cursor, see `MemoPageRequest`.
+#[tauri::command]
+pub async fn get_memo_page(app: AppHandle, request: MemoPageRequest) -> Result<MemoPage, String> {
+    log::trace!("[get_memo_page] request: {:?}", request);
+
+    with_state_blocking(&app, move |state| list_memo_page(state, &request)).await
+}
+
+pub fn list_memo_page(state: &AppState, request: &MemoPageRequest) -> Result<MemoPage, String> {
+    let after = match &request.cursor {
+        Some(cursor) => {
+            let cursor = decode_cursor(cursor)?;
+            if cursor.sort != request.sort {
+                return Err("Cursor belongs to another sort order".to_string());
             }
-        })
-        .filter(|x| {
-            if archived {
-                x.state == V1State::Archived
-            } else {
-                x.state == V1State::Normal
+            Some(cursor.key)
+        }
+        None => None,
+    };
+
+    let filter = ListFilter {
+        tag: &request.tag,
+        pinned: Some(request.pinned),
+        state: if request.archived {
+            V1State::Archived
+        } else {
+            V1State::Normal
+        },
+    };
+    // One more than asked tells whether there is a next page.
+    let mut keys =
+        state
+            .cache()
+            .index
+            .read()
+            .page(filter, request.sort, after.as_ref(), 0, request.limit + 1);
+    let has_more = keys.len() > request.limit;
+    keys.truncate(request.limit);
+
+    let next_cursor = match keys.last() {
+        Some(key) if has_more => Some(encode_cursor(&Cursor {
+            sort: request.sort,
+            key: key.clone(),
+            pinned: None,
+        })?),
+        _ => None,
+    };
+    let memo_names = keys.into_iter().map(|(_, name)| name).collect::<Vec<_>>();
+
+    Ok(MemoPage {
+        memos: load_memos(state, &memo_names)?,
+        next_cursor,
+    })
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FilteredMemoPageRequest {
+    #[serde(default)]
+    pub filter: MemoFilter,
+    /// All pinned memos before the others, each part in `sort` order.
+    #[serde(default)]
+    pub pinned_first: bool,
+    pub limit: usize,
+    #[serde(default)]
+    pub sort: MemoSort,
+    #[serde(default)]
+    pub cursor: Option<String>,
+}
+
+/// Pages through the cached memos matching a `MemoFilter`, without asking
+/// the server.
+#[tauri::command]
+pub async fn get_filtered_memo_page(
+    app: AppHandle,
+    request: FilteredMemoPageRequest,
+) -> Result<MemoPage, String> {
+    log::trace!("[get_filtered_memo_page] request: {:?}", request);
+
+    with_state_blocking(&app, move |state| filter_memo_page(state, &request)).await
+}
+
+/// Memos checked per batch when the filter needs more than the metadata.
+const FILTER_BATCH: usize = 64;
+
+pub fn filter_memo_page(
+    state: &AppState,
+    request: &FilteredMemoPageRequest,
+) -> Result<MemoPage, String> {
+    let filter = &request.filter;
+    let segments = match (filter.pinned, request.pinned_first) {
+        (Some(pinned), _) => vec![Some(pinned)],
+        (None, true) => vec![Some(true), Some(false)],
+        (None, false) => vec![None],
+    };
+
+    let (mut segment, mut after) = match &request.cursor {
+        Some(cursor) => {
+            let cursor = decode_cursor(cursor)?;
+            if cursor.sort != request.sort {
+                return Err("Cursor belongs to another sort order".to_string());
             }
-        })
+            let segment = segments
+                .iter()
+                .position(|x| *x == cursor.pinned)
+                .ok_or_else(|| "Cursor belongs to another filter".to_string())?;
+            (segment, Some(cursor.key))
+        }
+        None => (0, None),
+    };
+
+    let cache = state.cache();
+    let tag = filter
+        .tags
+        .as_ref()
+        .and_then(|x| x.required_tag())
+        .unwrap_or_default();
+    // One more than asked tells whether there is a next page.
+    let mut found = Vec::new();
+    while segment < segments.len() && found.len() <= request.limit {
+        let batch = if filter.needs_memo() {
+            FILTER_BATCH
+        } else {
+            request.limit + 1 - found.len()
+        };
+        let list = ListFilter {
+            tag,
+            pinned: segments[segment],
+            state: filter.state(),
+        };
+        let keys = cache
+            .index
+            .read()
+            .scan(list, request.sort, after.as_ref())
+            .filter(|(_, name)| {
+                cache
+                    .all_memo_meta
+                    .get(name)
+                    .is_some_and(|x| filter.matches_meta(&x))
+            })
+            .take(batch)
+            .cloned()
+            .collect::<Vec<_>>();
+
+        after = keys.last().cloned();
+        let done = keys.len() < batch;
+        if filter.needs_memo() {
+            let names = keys
+                .iter()
+                .map(|(_, name)| name.clone())
+                .collect::<Vec<_>>();
+            let memos = peek_memos(state, &names)?;
+            found.extend(
+                keys.into_iter()
+                    .filter(|(_, name)| memos.get(name).is_some_and(|x| filter.matches_memo(x)))
+                    .map(|key| (segment, key)),
+            );
+        } else {
+            found.extend(keys.into_iter().map(|key| (segment, key)));
+        }
+
+        if done {
+            segment += 1;
+            after = None;
+        }
+    }
+
+    let has_more = found.len() > request.limit;
+    found.truncate(request.limit);
+    let next_cursor = match found.last() {
+        Some((segment, key)) if has_more => Some(encode_cursor(&Cursor {
+            sort: request.sort,
+            key: key.clone(),
+            pinned: segments[*segment],
+        })?),
+        _ => None,
+    };
+    let memo_names = found
+        .into_iter()
+        .map(|(_, (_, name))| name)
         .collect::<Vec<_>>();
 
-    memo_list.sort_by(|a, b| b.display_time.cmp(&a.display_time));
-    let memo_meta_list = memo_list
-        .iter()
-        .skip(offset)
-        .take(limit)
-        .map(|x| x.name.clone())
+    Ok(MemoPage {
+        memos: load_memos(state, &memo_names)?,
+        next_cursor,
+    })
+}
+
+/// Bounds the in-memory memo cache, the rest is read from the memo store on
+/// demand. Applies to the current and later accounts.
+#[tauri::command]
+pub async fn set_memo_cache_limits(
+    state: State<'_, AppState>,
+    limits: CacheLimits,
+) -> Result<(), String> {
+    log::info!("[set_memo_cache_limits] limits: {:?}", limits);
+
+    let content = serde_json::to_string(&limits)
+        .map_err(|e| format!("Failed to serialize memo cache limits: {}", e))?;
+    state
+        .store
+        .data
+        .insert(MEMO_CACHE_LIMITS_KEY.to_string(), content);
+    save_store_data(&state).await?;
+
+    state.cache().memos.set_limits(limits);
+    Ok(())
+}
+
+pub fn list_memos(
+    state: &AppState,
+    offset: usize,
+    limit: usize,
+    tag: &str,
+    pinned: bool,
+    archived: bool,
+) -> Result<Option<Vec<Memo>>, String> {
+    let filter = ListFilter {
+        tag,
+        pinned: Some(pinned),
+        state: if archived {
+            V1State::Archived
+        } else {
+            V1State::Normal
+        },
+    };
+    let memo_names = state
+        .cache()
+        .index
+        .read()
+        .page(filter, MemoSort::default(), None, offset, limit)
+        .into_iter()
+        .map(|(_, name)| name)
         .collect::<Vec<_>>();
-    if memo_meta_list.is_empty() {
+    if memo_names.is_empty() {
         return Ok(None);
     }
 
-    let mut memo_list = Vec::with_capacity(memo_meta_list.len());
-    for memo_meta in memo_meta_list {
-        let m = get_memo(app.clone(), state.clone(), memo_meta).await?;
-        if let Some(m) = m {
-            memo_list.push(m);
+    Ok(Some(load_memos(state, &memo_names)?))
+}
+
+/// Like `load_memo` for many memos, reading the ones not in memory from the
+/// memo store in one batch. Keeps the order of `memo_names`.
+pub fn load_memos(state: &AppState, memo_names: &[String]) -> Result<Vec<Memo>, String> {
+    let cache = state.cache();
+    let mut memos = memo_names
+        .iter()
+        .map(|x| cache.memos.get(x))
+        .collect::<Vec<_>>();
+
+    let missing = memo_names
+        .iter()
+        .zip(&memos)
+        .filter(|(_, memo)| memo.is_none())
+        .map(|(name, _)| name.clone())
+        .collect::<Vec<_>>();
+    if !missing.is_empty() {
+        let mut loaded = state.memo_store.load_memos(&state.account()?, &missing)?;
+        for (name, memo) in memo_names.iter().zip(memos.iter_mut()) {
+            if memo.is_none()
+                && let Some(m) = loaded.remove(name)
+            {
+                cache.memos.insert(name.clone(), m.clone(), false);
+                *memo = Some(m);
+            }
+        }
+    }
+
+    Ok(memos.into_iter().flatten().collect())
+}
+
+/// Like `load_memos` but leaves the in-memory cache as it is, for scans
+/// over many memos.
+pub fn peek_memos(
+    state: &AppState,
+    memo_names: &[String],
+) -> Result<HashMap<String, Memo>, String> {
+    let cache = state.cache();
+    let mut memos = HashMap::new();
+    let mut missing = Vec::new();
+    for name in memo_names {
+        match cache.memos.peek(name) {
+            Some(memo) => {
+                memos.insert(name.clone(), memo);
+            }
+            None => missing.push(name.clone()),
         }
     }
 
-    Ok(Some(memo_list))
+    if !missing.is_empty() {
+        memos.extend(state.memo_store.load_memos(&state.account()?, &missing)?);
+    }
+    Ok(memos)
 }
 
-fn get_memo_cache_path(
+pub fn get_memo_cache_path(
     cache_path: &Path,
     server_url: &str,
     user_name: &str,
@@ -224,162 +496,202 @@ fn get_memo_cache_path(
         return Err("Host info not found, maybe not login".to_string());
     }
 
-    let path = sanitize_file_name(server_url) + "_" + &sanitize_file_name(user_name);
-
-    Ok(cache_path.join(path))
+    Ok(cache_path.join(get_profile_id(server_url, user_name)))
 }
 
-pub fn warm_up_memo_cache(app: &AppHandle, state: State<'_, AppState>) {
-    tauri::async_runtime::block_on(async {
-        let memo = get_memo_list(
-            app.clone(),
-            state.clone(),
-            0,
-            10,
-            "".to_string(),
-            false,
-            false,
-        );
-        let pinned_memo = get_memo_list(
-            app.clone(),
-            state.clone(),
-            0,
-            10,
-            "".to_string(),
-            true,
-            false,
-        );
-        let (memo, pinned_memo) = tokio::join!(memo, pinned_memo);
-        if let Err(e) = memo {
-            log::error!("Failed to warm up memo cache: {}", e);
-        }
-        if let Err(e) = pinned_memo {
-            log::error!("Failed to warm up pinned memo cache: {}", e);
-        }
-    });
+/// Profiles are keyed like their cache directories.
+pub fn get_profile_id(server_url: &str, user_name: &str) -> String {
+    sanitize_file_name(server_url) + "_" + &sanitize_file_name(user_name)
 }
 
-pub fn load_memo_meta_data(
-    app: &AppHandle,
-    server_url: &str,
-    user_name: &str,
-) -> Result<DashMap<String, MemoMeta>, String> {
-    log::debug!("load_memo_meta_data");
-    let app_cache_dir = get_memo_cache_path(
-        &app.path()
-            .app_cache_dir()
-            .map_err(|e| format!("Failed to get app cache dir: {}", e))?,
-        server_url,
-        user_name,
-    )
-    .map_err(|e| format!("Failed to get memo metadata cache path: {}", e))?;
-    let path = app_cache_dir.join("memos").join("all_memo_meta.json");
-
-    if !path.exists() {
-        return Ok(DashMap::new());
+pub fn warm_up_memo_cache(state: &AppState) {
+    if let Err(e) = list_memos(state, 0, 10, "", false, false) {
+        log::error!("Failed to warm up memo cache: {}", e);
+    }
+    if let Err(e) = list_memos(state, 0, 10, "", true, false) {
+        log::error!("Failed to warm up pinned memo cache: {}", e);
     }
+}
+
+/// Quiet time after a change before the cache is written.
+const PERSIST_DEBOUNCE: Duration = Duration::from_millis(200);
+/// Longest a change waits while changes keep coming in.
+const PERSIST_MAX_DELAY: Duration = Duration::from_secs(1);
 
-    let content = std::fs::read_to_string(&path)
-        .map_err(|e| format!("Failed to read get memo metadata cache: {}", e))?;
+/// Writes the cache whenever `CacheWriter` is notified, batching bursts of
+/// changes like a sync into one write.
+pub fn persist_memo_cache(app: AppHandle) {
+    let Some(mut rx) = app.state::<AppState>().cache_writer.rx.lock().take() else {
+        return;
+    };
 
-    let memo_meta: DashMap<String, MemoMeta> = serde_json::from_str(&content)
-        .map_err(|e| format!("Failed to parse get memo metadata cache: {}", e))?;
+    tauri::async_runtime::spawn(async move {
+        while rx.recv().await.is_some() {
+            let deadline = Instant::now() + PERSIST_MAX_DELAY;
+            loop {
+                let wait = PERSIST_DEBOUNCE.min(deadline.saturating_duration_since(Instant::now()));
+                match tokio::time::timeout(wait, rx.recv()).await {
+                    Ok(Some(())) => continue,
+                    _ => break,
+                }
+            }
 
-    Ok(memo_meta)
+            let flushed = with_state_blocking(&app, |state| {
+                flush_active_cache(state);
+                Ok(())
+            });
+            if let Err(e) = flushed.await {
+                log::error!("Failed to persist memo cache: {}", e);
+            }
+        }
+    });
 }
 
-pub fn persist_memo_cache(
-    cache_path: PathBuf,
-    cache: Arc<CacheData>,
-    server_url: Arc<RwLock<String>>,
-    user_name: Arc<RwLock<String>>,
-) {
-    tauri::async_runtime::spawn(async move {
-        let mut created_dir = false;
-        loop {
-            tokio::time::sleep(std::time::Duration::from_secs(1)).await;
+/// Writes the pending changes of the active account right away, used when
+/// the app exits or goes to the background.
+pub fn flush_active_cache(state: &AppState) {
+    let _writer = state.cache_writer.lock.lock();
 
-            let server_url_clone = server_url.read().clone();
-            let user_name_clone = user_name.read().clone();
+    // Profile switches swap the cache and the account under the cache
+    // lock, read both under it so a cache is never written elsewhere.
+    let (cache, account) = {
+        let cache = state.cache.read();
+        (cache.clone(), state.account())
+    };
 
-            if let Ok(memo_cache_dir) =
-                get_memo_cache_path(&cache_path, &server_url_clone, &user_name_clone)
-            {
-                let memo_cache_dir = memo_cache_dir.join("memos");
-
-                if !created_dir {
-                    created_dir = true;
-                    if let Err(e) = tokio::fs::create_dir_all(&memo_cache_dir)
-                        .await
-                        .map_err(|e| format!("Failed to create memo cache dir: {}", e))
-                    {
-                        log::error!(
-                            "Failed to create memo cache dir, path: {}, error: {}",
-                            memo_cache_dir.display(),
-                            e
-                        );
-                        continue;
-                    }
-                }
+    if let Ok(account) = account {
+        flush_memo_cache(&*state.memo_store, &account, &cache);
+    }
+}
 
-                if cache.is_all_memo_meta_updated.load(Ordering::Relaxed) {
-                    cache
-                        .is_all_memo_meta_updated
-                        .store(false, Ordering::Relaxed);
+/// Writes the memos and metadata changed since the last flush.
+pub fn flush_memo_cache(memo_store: &dyn MemoStore, account: &str, cache: &CacheData) {
+    let changed = std::mem::take(&mut *cache.changed_meta.lock());
+    if !changed.is_empty()
+        && let Err(e) = memo_store.save_meta(account, &cache.all_memo_meta, &changed)
+    {
+        log::error!("Failed to persist all_memo_meta, error: {}", e);
+        // Retried with the next flush.
+        cache.changed_meta.lock().extend(changed);
+    }
 
-                    let path = memo_cache_dir.join("all_memo_meta.json");
+    for (memo_name, memo, change) in cache.memos.dirty() {
+        match memo_store.save_memo(account, &memo) {
+            Ok(()) => cache.memos.mark_clean(&memo_name, change),
+            Err(e) => log::error!(
+                "Failed to persist memo cache, memo: {}, error: {}",
+                memo_name,
+                e
+            ),
+        }
+    }
+}
 
-                    let run = async {
-                        let cache = serde_json::to_string(&cache.all_memo_meta).map_err(|e| {
-                            format!("Failed to serialize all_memo_meta cache: {}", e)
-                        })?;
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn memo(name: &str, display_time: &str) -> Memo {
+        Memo {
+            name: name.to_string(),
+            display_time: display_time.to_string(),
+            content: name.to_string(),
+            state: V1State::Normal,
+            ..Default::default()
+        }
+    }
 
-                        tokio::fs::write(&path, cache)
-                            .await
-                            .map_err(|e| format!("Failed to write all_memo_meta cache: {}", e))?;
+    #[test]
+    fn test_memo_store_round_trip() {
+        let state = AppState::in_memory();
+
+        let cache = state.cache();
+        cache_memo(&state, "memos/1".to_string(), memo("memos/1", "1"));
+        cache_memo(&state, "memos/2".to_string(), memo("memos/2", "2"));
+        assert_eq!(cache.memos.dirty().len(), 2);
+        flush_active_cache(&state);
+        assert!(cache.memos.dirty().is_empty());
+        cache.memos.clear();
+
+        let list = list_memos(&state, 0, 10, "", false, false)
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            list.iter().map(|x| x.name.as_str()).collect::<Vec<_>>(),
+            ["memos/2", "memos/1"]
+        );
 
-                        Ok::<(), String>(())
-                    };
+        let mut request = MemoPageRequest {
+            tag: String::new(),
+            pinned: false,
+            archived: false,
+            limit: 1,
+            sort: MemoSort::default(),
+            cursor: None,
+        };
+        let page = list_memo_page(&state, &request).unwrap();
+        assert_eq!(page.memos[0].name, "memos/2");
+        request.cursor = page.next_cursor;
+        let page = list_memo_page(&state, &request).unwrap();
+        assert_eq!(page.memos[0].name, "memos/1");
+        assert_eq!(page.next_cursor, None);
+
+        uncache_memo(&state, "memos/1");
+        cache.memos.clear();
+        assert_eq!(load_memo(&state, "memos/1").unwrap(), None);
+        assert_eq!(
+            state
+                .memo_store
+                .load_meta(&state.account().unwrap())
+                .unwrap()
+                .len(),
+            1
+        );
+    }
 
-                    if let Err(e) = run.await {
-                        log::error!(
-                            "Failed to persist all_memo_meta, path: {}, error: {}",
-                            path.display(),
-                            e
-                        );
-                    }
-                }
+    #[test]
+    fn test_filtered_memo_page() {
+        let state = AppState::in_memory();
 
-                for v in cache.memos.iter() {
-                    if v.is_updated.load(Ordering::Relaxed) {
-                        v.is_updated.store(false, Ordering::Relaxed);
-
-                        let path = memo_cache_dir.join(
-                            "memo_".to_string() + &sanitize_file_name(&v.memo.name) + ".json",
-                        );
-
-                        let run = async {
-                            let cache = serde_json::to_string(&v.memo)
-                                .map_err(|e| format!("Failed to serialize memo cache: {}", e))?;
-
-                            tokio::fs::write(&path, cache)
-                                .await
-                                .map_err(|e| format!("Failed to write memo cache: {}", e))?;
-
-                            Ok::<(), String>(())
-                        };
-
-                        if let Err(e) = run.await {
-                            log::error!(
-                                "Failed to persist memo cache, memo: {}, path: {}, error: {}",
-                                v.memo.name,
-                                path.display(),
-                                e
-                            )
-                        }
-                    }
-                }
-            }
+        for (i, pinned) in [false, true, false, true, false].into_iter().enumerate() {
+            let name = format!("memos/{}", i);
+            let mut memo = memo(&name, &format!("2024-01-0{}T00:00:00Z", i + 1));
+            memo.pinned = pinned;
+            memo.content = if i == 2 { "todo" } else { "done" }.to_string();
+            cache_memo(&state, name, memo);
         }
-    });
+        flush_active_cache(&state);
+        state.cache().memos.clear();
+
+        let mut request = FilteredMemoPageRequest {
+            filter: MemoFilter::default(),
+            pinned_first: true,
+            limit: 3,
+            sort: MemoSort::default(),
+            cursor: None,
+        };
+        let names = |page: &MemoPage| {
+            page.memos
+                .iter()
+                .map(|x| x.name.clone())
+                .collect::<Vec<_>>()
+        };
+        let page = filter_memo_page(&state, &request).unwrap();
+        assert_eq!(names(&page), ["memos/3", "memos/1", "memos/4"]);
+        request.cursor = page.next_cursor;
+        let page = filter_memo_page(&state, &request).unwrap();
+        assert_eq!(names(&page), ["memos/2", "memos/0"]);
+        assert_eq!(page.next_cursor, None);
+
+        request.pinned_first = false;
+        request.cursor = None;
+        let page = filter_memo_page(&state, &request).unwrap();
+        assert_eq!(names(&page), ["memos/4", "memos/3", "memos/2"]);
+
+        request.filter.content = Some("DONE".to_string());
+        request.filter.display_time.before = Some("2024-01-05T00:00:00Z".to_string());
+        let page = filter_memo_page(&state, &request).unwrap();
+        assert_eq!(names(&page), ["memos/3", "memos/1", "memos/0"]);
+        assert_eq!(page.next_cursor, None);
+    }
 }
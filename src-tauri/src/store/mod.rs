@@ -1,28 +1,32 @@
-use crate::store::model::{AppState, StoreData};
-use std::path::PathBuf;
-use tauri::{AppHandle, Manager, State};
-
+use crate::{
+    store::{
+        backend::KvStore,
+        model::{AppState, StoreData},
+    },
+    sync::auth::AuthState,
+};
+use tauri::{AppHandle, Manager, Runtime, State};
+
+pub mod backend;
+pub mod cel;
+pub mod filter;
+pub mod index;
+pub mod lru;
 pub mod memo;
 pub mod model;
+pub mod outbox;
+pub mod profile;
+pub mod schema;
+pub mod search;
+pub mod secret;
 
-pub fn load_store_data(app: &AppHandle) -> Result<StoreData, String> {
+pub fn load_store_data(kv_store: &dyn KvStore) -> Result<StoreData, String> {
     log::debug!("load_store_data");
-    let store_path = get_store_path(app)?;
 
-    if !store_path.exists() {
+    let Some(store_data) = kv_store.load()? else {
         log::info!("Store file does not exist, creating default store data");
         return Ok(StoreData::default());
-    }
-
-    let content = std::fs::read_to_string(&store_path)
-        .map_err(|e| format!("Failed to read store file: {}", e))?;
-
-    let store_data: StoreData = serde_json::from_str(&content)
-        .map_err(|e| {
-            log::warn!("Failed to parse store file, using default: {}", e);
-            StoreData::default()
-        })
-        .unwrap_or_default();
+    };
 
     log::info!(
         "Successfully loaded store data with {} entries",
@@ -31,44 +35,37 @@ pub fn load_store_data(app: &AppHandle) -> Result<StoreData, String> {
     Ok(store_data)
 }
 
-fn get_store_path(app: &AppHandle) -> Result<PathBuf, String> {
-    let app_data_dir = app
-        .path()
-        .app_data_dir()
-        .map_err(|e| format!("Failed to get app data dir: {}", e))?;
-
-    // Ensure directory exists
-    std::fs::create_dir_all(&app_data_dir)
-        .map_err(|e| format!("Failed to create app data dir: {}", e))?;
-
-    Ok(app_data_dir.join("store.json"))
-}
-
-async fn async_get_store_path(app: &AppHandle) -> Result<PathBuf, String> {
-    let app_data_dir = app
-        .path()
-        .app_data_dir()
-        .map_err(|e| format!("Failed to get app data dir: {}", e))?;
-
-    // Ensure directory exists
-    tokio::fs::create_dir_all(&app_data_dir)
+/// Saves a snapshot of the store on the blocking pool, the backends write
+/// files synchronously.
+pub async fn save_store_data(state: &AppState) -> Result<(), String> {
+    let _writer = state.store_writer.lock().await;
+    let kv_store = state.kv_store.clone();
+    let store = state.store.clone();
+    tokio::task::spawn_blocking(move || kv_store.save(&store))
         .await
-        .map_err(|e| format!("Failed to create app data dir: {}", e))?;
-
-    Ok(app_data_dir.join("store.json"))
+        .map_err(|e| format!("Failed to save store data: {}", e))?
 }
 
-pub async fn save_store_data(app: &AppHandle, store_data: &StoreData) -> Result<(), String> {
-    let store_path = async_get_store_path(app).await?;
-
-    let content = serde_json::to_string(store_data)
-        .map_err(|e| format!("Failed to serialize store data: {}", e))?;
-
-    tokio::fs::write(&store_path, content)
+/// Runs `f` on the blocking pool with the app state, for memo store reads
+/// and writes that would otherwise stall the async runtime.
+pub async fn with_state_blocking<R, T, F>(app: &AppHandle<R>, f: F) -> Result<T, String>
+where
+    R: Runtime,
+    T: Send + 'static,
+    F: FnOnce(&AppState) -> Result<T, String> + Send + 'static,
+{
+    let app = app.clone();
+    tokio::task::spawn_blocking(move || f(&app.state::<AppState>()))
         .await
-        .map_err(|e| format!("Failed to write store file: {}", e))?;
+        .map_err(|e| format!("Failed to run memo store task: {}", e))?
+}
 
-    Ok(())
+/// Files that failed to parse or migrate on startup, so the frontend can tell
+/// the user why they were logged out or lost pending edits.
+#[tauri::command]
+pub async fn get_load_errors(state: State<'_, AppState>) -> Result<Vec<String>, String> {
+    log::trace!("[get_load_errors]");
+    Ok(state.load_errors.read().clone())
 }
 
 #[tauri::command]
@@ -84,10 +81,14 @@ pub async fn store_data(
         *state.store.server_url.write() = data.clone();
     } else if key == "userName" {
         *state.store.user_name.write() = data.clone();
+    } else if key == "accessToken" {
+        // A new login resumes whatever an expired token paused.
+        app.state::<AuthState>().reset();
+        state.outbox().notify.notify_one();
     }
 
     state.store.data.insert(key, data);
-    save_store_data(&app, &state.store).await?;
+    save_store_data(&state).await?;
 
     Ok(())
 }
@@ -99,11 +100,7 @@ pub async fn get_data(state: State<'_, AppState>, key: String) -> Result<Option<
 }
 
 #[tauri::command]
-pub async fn remove_data(
-    app: AppHandle,
-    state: State<'_, AppState>,
-    keys: Vec<String>,
-) -> Result<(), String> {
+pub async fn remove_data(state: State<'_, AppState>, keys: Vec<String>) -> Result<(), String> {
     log::info!("[remove_data] keys: {:?}", keys);
 
     if keys.contains(&"serverUrl".to_string()) {
@@ -117,7 +114,7 @@ pub async fn remove_data(
         state.store.data.remove(&key);
     }
 
-    save_store_data(&app, &state.store).await?;
+    save_store_data(&state).await?;
 
     Ok(())
 }
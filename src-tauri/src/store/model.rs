@@ -1,16 +1,56 @@
-use std::sync::{Arc, atomic::AtomicBool};
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Arc,
+};
 
 use dashmap::DashMap;
-use parking_lot::RwLock;
+use parking_lot::{Mutex, RwLock};
 use serde::{Deserialize, Serialize};
+use tokio::sync::mpsc;
 
-use crate::api::models::{V1MemoRelation, V1Reaction, V1Resource, V1State};
+use crate::{
+    api::models::{Apiv1Memo, V1MemoProperty, V1MemoRelation, V1Reaction, V1Resource, V1State},
+    markdown::analysis::analyse,
+    store::{
+        backend::{KvStore, MemoStore},
+        index::MemoIndex,
+        lru::{CacheLimits, MemoLru},
+        memo::get_profile_id,
+        outbox::Outbox,
+    },
+};
 
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct StoreData {
     pub data: DashMap<String, String>,
     pub server_url: Arc<RwLock<String>>,
     pub user_name: Arc<RwLock<String>>,
+    /// Accounts that are logged in but not active.
+    #[serde(default)]
+    pub profiles: Arc<RwLock<Vec<Profile>>>,
+}
+
+/// `StoreData::data` key of the JSON `CacheLimits`.
+pub const MEMO_CACHE_LIMITS_KEY: &str = "memoCacheLimits";
+
+impl StoreData {
+    pub fn memo_cache_limits(&self) -> CacheLimits {
+        self.data
+            .get(MEMO_CACHE_LIMITS_KEY)
+            .and_then(|x| serde_json::from_str(&x).ok())
+            .unwrap_or_default()
+    }
+}
+
+/// A logged in account parked by `switch_profile`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct Profile {
+    pub id: String,
+    pub server_url: String,
+    pub user_name: String,
+    /// The account scoped keys of `StoreData::data`, see `PROFILE_KEYS`.
+    pub data: HashMap<String, String>,
 }
 
 #[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
@@ -30,13 +70,76 @@ pub struct Memo {
     pub reactions: Vec<V1Reaction>,
     pub tags: Vec<String>,
     pub state: V1State,
+    /// As the server computed it, or `analyse_content` for local edits.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub property: Option<V1MemoProperty>,
+    /// Server version the pending local edits started from, `None` when the
+    /// cached memo matches the server.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub base: Option<MemoBase>,
 }
 
-#[derive(Debug, Default, Serialize, Deserialize)]
-pub struct MemoDataCache {
-    pub memo: Memo,
-    #[serde(skip)]
-    pub is_updated: AtomicBool,
+impl Memo {
+    /// Derives `tags` and `property` from `content` like the server does on
+    /// save, for edits it has not seen yet.
+    pub fn analyse_content(&mut self) {
+        let analysis = analyse(&self.content);
+        self.tags = analysis.tags;
+        self.property = Some(analysis.property);
+    }
+
+    /// `property`, analysed from the content if the server sent none.
+    pub fn property(&self) -> V1MemoProperty {
+        match &self.property {
+            Some(property) => property.clone(),
+            None => analyse(&self.content).property,
+        }
+    }
+}
+
+/// The parts of a memo the three-way merge needs from the common ancestor.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct MemoBase {
+    pub update_time: String,
+    pub content: String,
+    pub tags: Vec<String>,
+    pub pinned: bool,
+}
+
+impl From<&Memo> for MemoBase {
+    fn from(memo: &Memo) -> Self {
+        MemoBase {
+            update_time: memo.update_time.clone(),
+            content: memo.content.clone(),
+            tags: memo.tags.clone(),
+            pinned: memo.pinned,
+        }
+    }
+}
+
+impl From<Apiv1Memo> for Memo {
+    fn from(memo: Apiv1Memo) -> Self {
+        Memo {
+            name: memo.name.unwrap_or_default(),
+            create_time: memo.create_time.unwrap_or_default(),
+            update_time: memo.update_time.unwrap_or_default(),
+            display_time: memo.display_time.unwrap_or_default(),
+            visibility: memo
+                .visibility
+                .map(|v| v.to_string())
+                .unwrap_or_else(|| "PRIVATE".to_string()),
+            content: memo.content.unwrap_or_default(),
+            pinned: memo.pinned.unwrap_or_default(),
+            resources: memo.resources.unwrap_or_default(),
+            relations: memo.relations.unwrap_or_default(),
+            reactions: memo.reactions.unwrap_or_default(),
+            tags: memo.tags.unwrap_or_default(),
+            state: memo.state.unwrap_or(V1State::Normal),
+            property: memo.property.map(|x| *x),
+            base: None,
+        }
+    }
 }
 
 #[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
@@ -51,15 +154,173 @@ pub struct MemoMeta {
     pub state: V1State,
 }
 
+impl From<&Memo> for MemoMeta {
+    fn from(memo: &Memo) -> Self {
+        MemoMeta {
+            name: memo.name.clone(),
+            create_time: memo.create_time.clone(),
+            update_time: memo.update_time.clone(),
+            display_time: memo.display_time.clone(),
+            visibility: memo.visibility.clone(),
+            pinned: memo.pinned,
+            tags: memo.tags.clone(),
+            state: memo.state,
+        }
+    }
+}
+
+/// Change `all_memo_meta` through `put_meta` and `remove_meta` only, they
+/// keep `index` in step.
 #[derive(Debug, Default)]
 pub struct CacheData {
-    pub memos: DashMap<String, MemoDataCache>,
+    pub memos: MemoLru,
     pub all_memo_meta: DashMap<String, MemoMeta>,
-    pub is_all_memo_meta_updated: AtomicBool,
+    /// Names whose metadata changed since the last flush.
+    pub changed_meta: Mutex<HashSet<String>>,
+    pub index: RwLock<MemoIndex>,
 }
 
-#[derive(Debug, Default)]
+impl CacheData {
+    pub fn new(limits: CacheLimits, all_memo_meta: DashMap<String, MemoMeta>) -> Self {
+        let mut index = MemoIndex::default();
+        for x in all_memo_meta.iter() {
+            index.insert(x.value());
+        }
+
+        Self {
+            memos: MemoLru::new(limits),
+            all_memo_meta,
+            changed_meta: Default::default(),
+            index: RwLock::new(index),
+        }
+    }
+
+    /// Returns whether the metadata changed.
+    pub fn put_meta(&self, memo_name: String, meta: MemoMeta) -> bool {
+        // Held across both maps so concurrent puts can't leave stale entries.
+        let mut index = self.index.write();
+        if let Some(old) = self.all_memo_meta.get(&memo_name) {
+            if *old == meta {
+                return false;
+            }
+            index.remove(&old);
+        }
+
+        index.insert(&meta);
+        self.changed_meta.lock().insert(memo_name.clone());
+        self.all_memo_meta.insert(memo_name, meta);
+        true
+    }
+
+    pub fn remove_meta(&self, memo_name: &str) {
+        let mut index = self.index.write();
+        if let Some((_, old)) = self.all_memo_meta.remove(memo_name) {
+            index.remove(&old);
+        }
+        self.changed_meta.lock().insert(memo_name.to_string());
+    }
+
+    /// Forgets the metadata without deleting it from the memo store.
+    pub fn clear_meta(&self) {
+        let mut index = self.index.write();
+        self.all_memo_meta.clear();
+        self.changed_meta.lock().clear();
+        index.clear();
+    }
+}
+
+/// Wakes the cache writer of `persist_memo_cache` on every change.
+#[derive(Debug)]
+pub struct CacheWriter {
+    tx: mpsc::UnboundedSender<()>,
+    pub rx: Mutex<Option<mpsc::UnboundedReceiver<()>>>,
+    /// Held while flushing, so a flush on exit waits for a running one.
+    pub lock: Mutex<()>,
+}
+
+impl Default for CacheWriter {
+    fn default() -> Self {
+        let (tx, rx) = mpsc::unbounded_channel();
+        Self {
+            tx,
+            rx: Mutex::new(Some(rx)),
+            lock: Mutex::new(()),
+        }
+    }
+}
+
+impl CacheWriter {
+    pub fn notify(&self) {
+        let _ = self.tx.send(());
+    }
+}
+
+/// `cache` and `outbox` belong to the active account and are replaced as a
+/// whole when switching profiles, hold on to the `Arc` for one operation only.
+#[derive(Debug)]
 pub struct AppState {
     pub store: StoreData,
-    pub cache: Arc<CacheData>,
+    pub cache: Arc<RwLock<Arc<CacheData>>>,
+    pub outbox: RwLock<Arc<Outbox>>,
+    pub kv_store: Arc<dyn KvStore>,
+    pub memo_store: Arc<dyn MemoStore>,
+    pub cache_writer: CacheWriter,
+    /// Held while saving the store, so saves reach the disk in call order.
+    pub store_writer: tokio::sync::Mutex<()>,
+    /// Persisted data that failed to load and was replaced by defaults.
+    pub load_errors: RwLock<Vec<String>>,
+}
+
+impl AppState {
+    pub fn new(
+        store: StoreData,
+        kv_store: Arc<dyn KvStore>,
+        memo_store: Arc<dyn MemoStore>,
+    ) -> Self {
+        let cache = CacheData::new(store.memo_cache_limits(), DashMap::new());
+        Self {
+            store,
+            cache: Arc::new(RwLock::new(Arc::new(cache))),
+            outbox: Default::default(),
+            kv_store,
+            memo_store,
+            cache_writer: Default::default(),
+            store_writer: Default::default(),
+            load_errors: Default::default(),
+        }
+    }
+
+    /// A state logged in to a fake server, on memory backed stores.
+    #[cfg(test)]
+    pub fn in_memory() -> Self {
+        use crate::store::backend::memory::{MemoryKvStore, MemoryMemoStore};
+
+        let state = Self::new(
+            StoreData::default(),
+            Arc::new(MemoryKvStore::default()),
+            Arc::new(MemoryMemoStore::default()),
+        );
+        *state.store.server_url.write() = "https://memos.example.com".to_string();
+        *state.store.user_name.write() = "users/1".to_string();
+        state
+    }
+
+    /// Id of the logged in account, memos are stored under it.
+    pub fn account(&self) -> Result<String, String> {
+        let server_url = self.store.server_url.read();
+        let user_name = self.store.user_name.read();
+        if server_url.is_empty() || user_name.is_empty() {
+            return Err("Host info not found, maybe not login".to_string());
+        }
+
+        Ok(get_profile_id(&server_url, &user_name))
+    }
+
+    pub fn cache(&self) -> Arc<CacheData> {
+        self.cache.read().clone()
+    }
+
+    pub fn outbox(&self) -> Arc<Outbox> {
+        self.outbox.read().clone()
+    }
 }
@@ -0,0 +1,756 @@
+use std::path::{Path, PathBuf};
+
+use chrono::{DateTime, SecondsFormat, Utc};
+use parking_lot::RwLock;
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Manager, Runtime, State};
+
+use crate::{
+    api::models::{V1Resource, V1State},
+    store::{
+        memo::{cache_memo, get_memo_cache_path, load_memo, uncache_memo},
+        model::{AppState, Memo, MemoBase},
+        schema,
+    },
+};
+
+pub const LOCAL_MEMO_PREFIX: &str = "memos/local-";
+
+const RETRY_BASE_SECS: i64 = 5;
+const RETRY_MAX_SECS: i64 = 30 * 60;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum OutboxOp {
+    #[serde(rename_all = "camelCase")]
+    Create {
+        memo_name: String,
+        content: String,
+        visibility: String,
+        resources: Vec<V1Resource>,
+    },
+    #[serde(rename_all = "camelCase")]
+    Update {
+        memo_name: String,
+        content: Option<String>,
+        visibility: Option<String>,
+    },
+    #[serde(rename_all = "camelCase")]
+    SetState { memo_name: String, state: V1State },
+    #[serde(rename_all = "camelCase")]
+    Pin { memo_name: String, pinned: bool },
+    #[serde(rename_all = "camelCase")]
+    Delete { memo_name: String },
+}
+
+impl OutboxOp {
+    pub fn memo_name(&self) -> &str {
+        match self {
+            OutboxOp::Create { memo_name, .. }
+            | OutboxOp::Update { memo_name, .. }
+            | OutboxOp::SetState { memo_name, .. }
+            | OutboxOp::Pin { memo_name, .. }
+            | OutboxOp::Delete { memo_name } => memo_name,
+        }
+    }
+
+    /// The operation without its content edit, `None` if nothing is left.
+    fn without_content(self) -> Option<OutboxOp> {
+        match self {
+            OutboxOp::Update {
+                memo_name,
+                visibility: Some(visibility),
+                ..
+            } => Some(OutboxOp::Update {
+                memo_name,
+                content: None,
+                visibility: Some(visibility),
+            }),
+            OutboxOp::Update { .. } => None,
+            op => Some(op),
+        }
+    }
+
+    fn set_memo_name(&mut self, name: String) {
+        match self {
+            OutboxOp::Create { memo_name, .. }
+            | OutboxOp::Update { memo_name, .. }
+            | OutboxOp::SetState { memo_name, .. }
+            | OutboxOp::Pin { memo_name, .. }
+            | OutboxOp::Delete { memo_name } => *memo_name = name,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OutboxEntry {
+    pub id: u64,
+    pub op: OutboxOp,
+    pub created_at: DateTime<Utc>,
+    pub attempts: u32,
+    pub next_retry_at: Option<DateTime<Utc>>,
+    pub last_error: Option<String>,
+    /// Being sent to the server, see `Outbox::begin`. Nothing is in flight
+    /// after a restart.
+    #[serde(skip)]
+    pub in_flight: bool,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OutboxData {
+    pub next_id: u64,
+    /// Pending operations, replayed in order.
+    pub entries: Vec<OutboxEntry>,
+    /// Operations the server rejected for good, kept so no edit is lost silently.
+    pub failed: Vec<OutboxEntry>,
+    #[serde(default)]
+    pub conflicts: Vec<MemoConflict>,
+}
+
+/// A memo changed both locally and on the server in ways the three-way merge
+/// could not reconcile. Both versions are kept until the user picks one.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MemoConflict {
+    pub memo_name: String,
+    pub base: MemoBase,
+    pub local: Memo,
+    pub remote: Memo,
+    pub detected_at: DateTime<Utc>,
+    /// Queued operations other than content edits, queued again once the
+    /// conflict is resolved.
+    #[serde(default)]
+    pub pending: Vec<OutboxOp>,
+}
+
+/// Durable queue of memo changes made while offline.
+#[derive(Debug, Default)]
+pub struct Outbox {
+    pub data: RwLock<OutboxData>,
+    /// Woken whenever an operation is queued.
+    pub notify: tokio::sync::Notify,
+    /// Held while saving, so saves reach the disk in call order.
+    pub writer: tokio::sync::Mutex<()>,
+}
+
+impl Outbox {
+    pub fn new(data: OutboxData) -> Self {
+        Self {
+            data: RwLock::new(data),
+            notify: tokio::sync::Notify::new(),
+            writer: Default::default(),
+        }
+    }
+
+    pub fn has_pending(&self, memo_name: &str) -> bool {
+        self.data
+            .read()
+            .entries
+            .iter()
+            .any(|x| x.op.memo_name() == memo_name)
+    }
+
+    /// Queues an operation, folding it into a pending create or update of the
+    /// same memo where possible. Entries in flight are never changed, the
+    /// operation is queued after them instead.
+    pub fn push(&self, op: OutboxOp) {
+        {
+            let mut data = self.data.write();
+
+            let memo_name = op.memo_name().to_string();
+            let created_offline = data.entries.iter().any(|x| {
+                !x.in_flight
+                    && matches!(&x.op, OutboxOp::Create { memo_name: name, .. } if *name == memo_name)
+            });
+            let pending = data
+                .entries
+                .iter_mut()
+                .rev()
+                .find(|x| x.op.memo_name() == memo_name)
+                .filter(|x| {
+                    !x.in_flight
+                        && matches!(x.op, OutboxOp::Create { .. } | OutboxOp::Update { .. })
+                });
+
+            match (pending, &op) {
+                (_, OutboxOp::Delete { .. }) if created_offline => {
+                    // The server never saw this memo, forget about it.
+                    data.entries.retain(|x| x.op.memo_name() != memo_name);
+                }
+                (
+                    Some(entry),
+                    OutboxOp::Update {
+                        content: new_content,
+                        visibility: new_visibility,
+                        ..
+                    },
+                ) => match &mut entry.op {
+                    OutboxOp::Create {
+                        content,
+                        visibility,
+                        ..
+                    } => {
+                        if let Some(new_content) = new_content {
+                            *content = new_content.clone();
+                        }
+                        if let Some(new_visibility) = new_visibility {
+                            *visibility = new_visibility.clone();
+                        }
+                    }
+                    OutboxOp::Update {
+                        content,
+                        visibility,
+                        ..
+                    } => {
+                        if new_content.is_some() {
+                            *content = new_content.clone();
+                        }
+                        if new_visibility.is_some() {
+                            *visibility = new_visibility.clone();
+                        }
+                    }
+                    _ => {}
+                },
+                _ => {
+                    let id = data.next_id;
+                    data.next_id += 1;
+                    data.entries.push(OutboxEntry {
+                        id,
+                        op,
+                        created_at: Utc::now(),
+                        attempts: 0,
+                        next_retry_at: None,
+                        last_error: None,
+                        in_flight: false,
+                    });
+                }
+            }
+        }
+
+        self.notify.notify_one();
+    }
+
+    pub fn front(&self) -> Option<OutboxEntry> {
+        self.data.read().entries.first().cloned()
+    }
+
+    /// The front entry, marked in flight unless its retry is not due yet.
+    /// Marking it under the lock `push` takes keeps later operations from
+    /// being folded into what is already on its way.
+    pub fn begin(&self) -> Option<OutboxEntry> {
+        let mut data = self.data.write();
+        let entry = data.entries.first_mut()?;
+        if entry.next_retry_at.is_none_or(|x| x <= Utc::now()) {
+            entry.in_flight = true;
+        }
+        Some(entry.clone())
+    }
+
+    /// Whether a delete of the memo is queued.
+    pub fn is_deleted(&self, memo_name: &str) -> bool {
+        self.data
+            .read()
+            .entries
+            .iter()
+            .any(|x| matches!(&x.op, OutboxOp::Delete { memo_name: name } if name == memo_name))
+    }
+
+    /// Drops the replayed entry and points later entries of a created local
+    /// memo at the name the server assigned.
+    pub fn complete(&self, id: u64, renamed: Option<(&str, &str)>) {
+        let mut data = self.data.write();
+        data.entries.retain(|x| x.id != id);
+        if let Some((from, to)) = renamed {
+            for entry in data.entries.iter_mut() {
+                if entry.op.memo_name() == from {
+                    entry.op.set_memo_name(to.to_string());
+                }
+            }
+        }
+    }
+
+    /// Schedules the entry for another attempt with exponential backoff.
+    pub fn retry_later(&self, id: u64, error: String) {
+        let mut data = self.data.write();
+        if let Some(entry) = data.entries.iter_mut().find(|x| x.id == id) {
+            let delay = (RETRY_BASE_SECS << entry.attempts.min(16)).min(RETRY_MAX_SECS);
+            entry.in_flight = false;
+            entry.attempts += 1;
+            entry.next_retry_at = Some(Utc::now() + chrono::Duration::seconds(delay));
+            entry.last_error = Some(error);
+        }
+    }
+
+    /// Parks an unmergeable memo as a conflict. Its queued content edits are
+    /// part of the local version, its other operations wait in `pending`.
+    pub fn add_conflict(&self, mut conflict: MemoConflict) {
+        let mut data = self.data.write();
+        let (parked, entries): (Vec<_>, Vec<_>) = std::mem::take(&mut data.entries)
+            .into_iter()
+            .partition(|x| x.op.memo_name() == conflict.memo_name);
+        data.entries = entries;
+
+        let mut pending = match data
+            .conflicts
+            .iter()
+            .position(|x| x.memo_name == conflict.memo_name)
+        {
+            Some(pos) => data.conflicts.remove(pos).pending,
+            None => Vec::new(),
+        };
+        pending.extend(parked.into_iter().filter_map(|x| x.op.without_content()));
+        pending.extend(conflict.pending);
+        conflict.pending = pending;
+        data.conflicts.push(conflict);
+    }
+
+    pub fn fail(&self, id: u64, error: String) {
+        let mut data = self.data.write();
+        if let Some(pos) = data.entries.iter().position(|x| x.id == id) {
+            let mut entry = data.entries.remove(pos);
+            entry.in_flight = false;
+            entry.attempts += 1;
+            entry.last_error = Some(error);
+            data.failed.push(entry);
+        }
+    }
+}
+
+pub fn is_local_memo(memo_name: &str) -> bool {
+    memo_name.starts_with(LOCAL_MEMO_PREFIX)
+}
+
+fn now_timestamp() -> String {
+    Utc::now().to_rfc3339_opts(SecondsFormat::Secs, true)
+}
+
+fn get_outbox_path(data_path: &Path, server_url: &str, user_name: &str) -> Result<PathBuf, String> {
+    Ok(get_memo_cache_path(data_path, server_url, user_name)?.join("outbox.json"))
+}
+
+/// The outbox lives in the app data dir, unlike the memo cache it must
+/// survive the OS purging caches.
+pub fn load_outbox(app: &AppHandle, server_url: &str, user_name: &str) -> Result<Outbox, String> {
+    log::debug!("load_outbox");
+    let path = get_outbox_path(
+        &app.path()
+            .app_data_dir()
+            .map_err(|e| format!("Failed to get app data dir: {}", e))?,
+        server_url,
+        user_name,
+    )?;
+
+    let data = schema::OUTBOX
+        .read::<OutboxData>(&path)
+        .map_err(|e| format!("Failed to read outbox: {}", e))?
+        .unwrap_or_default();
+
+    Ok(Outbox::new(data))
+}
+
+pub async fn save_outbox<R: Runtime>(app: &AppHandle<R>, state: &AppState) -> Result<(), String> {
+    let path = get_outbox_path(
+        &app.path()
+            .app_data_dir()
+            .map_err(|e| format!("Failed to get app data dir: {}", e))?,
+        &state.store.server_url.read(),
+        &state.store.user_name.read(),
+    )?;
+
+    if let Some(parent) = path.parent() {
+        tokio::fs::create_dir_all(parent)
+            .await
+            .map_err(|e| format!("Failed to create outbox dir: {}", e))?;
+    }
+
+    let outbox = state.outbox();
+    let _writer = outbox.writer.lock().await;
+    let content = serde_json::to_string(&*outbox.data.read())
+        .map_err(|e| format!("Failed to serialize outbox: {}", e))?;
+
+    schema::OUTBOX
+        .write_str_async(path, content)
+        .await
+        .map_err(|e| format!("Failed to write outbox: {}", e))
+}
+
+async fn enqueue(app: &AppHandle, state: &AppState, op: OutboxOp) -> Result<(), String> {
+    log::info!("[outbox] queue {:?}", op);
+    state.outbox().push(op);
+    save_outbox(app, state).await
+}
+
+/// Loads a cached memo for a local edit, remembering the server version the
+/// edit starts from.
+fn get_cached_memo(state: &AppState, memo_name: &str) -> Result<Memo, String> {
+    let mut memo = load_memo(state, memo_name)?
+        .ok_or_else(|| format!("Memo not found in cache: {}", memo_name))?;
+
+    if memo.base.is_none() && !is_local_memo(memo_name) {
+        memo.base = Some(MemoBase::from(&memo));
+    }
+
+    Ok(memo)
+}
+
+#[tauri::command]
+pub async fn outbox_create_memo(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    content: String,
+    visibility: String,
+    resources: Vec<V1Resource>,
+) -> Result<Memo, String> {
+    let now = now_timestamp();
+    let memo_name = format!(
+        "{}{}",
+        LOCAL_MEMO_PREFIX,
+        Utc::now().timestamp_nanos_opt().unwrap_or_default()
+    );
+    log::trace!("[outbox_create_memo] memo: {}", memo_name);
+
+    let mut memo = Memo {
+        name: memo_name.clone(),
+        create_time: now.clone(),
+        update_time: now.clone(),
+        display_time: now,
+        visibility: visibility.clone(),
+        content: content.clone(),
+        pinned: false,
+        resources: resources.clone(),
+        relations: vec![],
+        reactions: vec![],
+        tags: vec![],
+        state: V1State::Normal,
+        property: None,
+        base: None,
+    };
+    memo.analyse_content();
+    cache_memo(&state, memo_name.clone(), memo.clone());
+
+    enqueue(
+        &app,
+        &state,
+        OutboxOp::Create {
+            memo_name,
+            content,
+            visibility,
+            resources,
+        },
+    )
+    .await?;
+
+    Ok(memo)
+}
+
+#[tauri::command]
+pub async fn outbox_update_memo(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    memo_name: String,
+    content: Option<String>,
+    visibility: Option<String>,
+) -> Result<Memo, String> {
+    log::trace!("[outbox_update_memo] memo: {}", memo_name);
+
+    let mut memo = get_cached_memo(&state, &memo_name)?;
+    if let Some(content) = &content {
+        memo.content = content.clone();
+        memo.analyse_content();
+    }
+    if let Some(visibility) = &visibility {
+        memo.visibility = visibility.clone();
+    }
+    memo.update_time = now_timestamp();
+    cache_memo(&state, memo_name.clone(), memo.clone());
+
+    enqueue(
+        &app,
+        &state,
+        OutboxOp::Update {
+            memo_name,
+            content,
+            visibility,
+        },
+    )
+    .await?;
+
+    Ok(memo)
+}
+
+#[tauri::command]
+pub async fn outbox_set_memo_state(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    memo_name: String,
+    memo_state: V1State,
+) -> Result<Memo, String> {
+    log::trace!("[outbox_set_memo_state] memo: {}", memo_name);
+
+    let mut memo = get_cached_memo(&state, &memo_name)?;
+    memo.state = memo_state;
+    memo.update_time = now_timestamp();
+    cache_memo(&state, memo_name.clone(), memo.clone());
+
+    enqueue(
+        &app,
+        &state,
+        OutboxOp::SetState {
+            memo_name,
+            state: memo_state,
+        },
+    )
+    .await?;
+
+    Ok(memo)
+}
+
+#[tauri::command]
+pub async fn outbox_pin_memo(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    memo_name: String,
+    pinned: bool,
+) -> Result<Memo, String> {
+    log::trace!("[outbox_pin_memo] memo: {}", memo_name);
+
+    let mut memo = get_cached_memo(&state, &memo_name)?;
+    memo.pinned = pinned;
+    memo.update_time = now_timestamp();
+    cache_memo(&state, memo_name.clone(), memo.clone());
+
+    enqueue(&app, &state, OutboxOp::Pin { memo_name, pinned }).await?;
+
+    Ok(memo)
+}
+
+#[tauri::command]
+pub async fn outbox_delete_memo(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    memo_name: String,
+) -> Result<(), String> {
+    log::info!("[outbox_delete_memo] memo: {}", memo_name);
+
+    uncache_memo(&state, &memo_name);
+
+    enqueue(&app, &state, OutboxOp::Delete { memo_name }).await
+}
+
+#[tauri::command]
+pub async fn get_outbox(state: State<'_, AppState>) -> Result<OutboxData, String> {
+    Ok(state.outbox().data.read().clone())
+}
+
+/// Drops operations the server rejected, after the user has seen them.
+#[tauri::command]
+pub async fn clear_failed_outbox(app: AppHandle, state: State<'_, AppState>) -> Result<(), String> {
+    log::info!("[clear_failed_outbox]");
+    state.outbox().data.write().failed.clear();
+    save_outbox(&app, &state).await
+}
+
+#[tauri::command]
+pub async fn get_memo_conflicts(state: State<'_, AppState>) -> Result<Vec<MemoConflict>, String> {
+    Ok(state.outbox().data.read().conflicts.clone())
+}
+
+/// Settles a conflict with the content the user picked or merged by hand and
+/// queues it as an edit on top of the server version.
+#[tauri::command]
+pub async fn resolve_memo_conflict(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    memo_name: String,
+    content: String,
+) -> Result<Memo, String> {
+    log::info!("[resolve_memo_conflict] memo: {}", memo_name);
+
+    let conflict = {
+        let outbox = state.outbox();
+        let mut data = outbox.data.write();
+        let pos = data
+            .conflicts
+            .iter()
+            .position(|x| x.memo_name == memo_name)
+            .ok_or_else(|| format!("Conflict not found: {}", memo_name))?;
+        data.conflicts.remove(pos)
+    };
+
+    let mut memo = conflict.remote;
+    memo.base = Some(MemoBase::from(&memo));
+    memo.content = content.clone();
+    memo.analyse_content();
+    let mut deleted = false;
+    for op in &conflict.pending {
+        match op {
+            OutboxOp::Update {
+                visibility: Some(visibility),
+                ..
+            } => memo.visibility = visibility.clone(),
+            OutboxOp::SetState { state, .. } => memo.state = *state,
+            OutboxOp::Pin { pinned, .. } => memo.pinned = *pinned,
+            OutboxOp::Delete { .. } => deleted = true,
+            _ => {}
+        }
+    }
+    if deleted {
+        uncache_memo(&state, &memo_name);
+    } else {
+        cache_memo(&state, memo_name.clone(), memo.clone());
+    }
+
+    enqueue(
+        &app,
+        &state,
+        OutboxOp::Update {
+            memo_name,
+            content: Some(content),
+            visibility: None,
+        },
+    )
+    .await?;
+    for op in conflict.pending {
+        enqueue(&app, &state, op).await?;
+    }
+
+    Ok(memo)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn update(memo_name: &str, content: &str) -> OutboxOp {
+        OutboxOp::Update {
+            memo_name: memo_name.to_string(),
+            content: Some(content.to_string()),
+            visibility: None,
+        }
+    }
+
+    #[test]
+    fn test_outbox_folds_into_pending_create() {
+        let outbox = Outbox::default();
+        let local = "memos/local-1";
+        outbox.push(OutboxOp::Create {
+            memo_name: local.to_string(),
+            content: "a".to_string(),
+            visibility: "PRIVATE".to_string(),
+            resources: vec![],
+        });
+        outbox.push(update(local, "b"));
+        outbox.push(OutboxOp::Pin {
+            memo_name: local.to_string(),
+            pinned: true,
+        });
+        outbox.push(update("memos/1", "c"));
+
+        let data = outbox.data.read().clone();
+        assert_eq!(data.entries.len(), 3);
+        assert!(matches!(&data.entries[0].op, OutboxOp::Create { content, .. } if content == "b"));
+
+        outbox.complete(data.entries[0].id, Some((local, "memos/2")));
+        assert_eq!(outbox.front().unwrap().op.memo_name(), "memos/2");
+
+        outbox.push(OutboxOp::Delete {
+            memo_name: "memos/2".to_string(),
+        });
+        assert_eq!(outbox.data.read().entries.len(), 3);
+    }
+
+    #[test]
+    fn test_outbox_drops_deleted_local_memo() {
+        let outbox = Outbox::default();
+        let local = "memos/local-1";
+        outbox.push(OutboxOp::Create {
+            memo_name: local.to_string(),
+            content: "a".to_string(),
+            visibility: "PRIVATE".to_string(),
+            resources: vec![],
+        });
+        outbox.push(OutboxOp::Pin {
+            memo_name: local.to_string(),
+            pinned: true,
+        });
+        outbox.push(OutboxOp::Delete {
+            memo_name: local.to_string(),
+        });
+
+        assert!(outbox.data.read().entries.is_empty());
+    }
+
+    #[test]
+    fn test_outbox_keeps_in_flight_create() {
+        let outbox = Outbox::default();
+        let local = "memos/local-1";
+        outbox.push(OutboxOp::Create {
+            memo_name: local.to_string(),
+            content: "a".to_string(),
+            visibility: "PRIVATE".to_string(),
+            resources: vec![],
+        });
+        let entry = outbox.begin().unwrap();
+        assert!(entry.in_flight);
+
+        outbox.push(update(local, "b"));
+        outbox.push(OutboxOp::Delete {
+            memo_name: local.to_string(),
+        });
+        let data = outbox.data.read().clone();
+        assert_eq!(data.entries.len(), 3);
+        assert!(matches!(&data.entries[0].op, OutboxOp::Create { content, .. } if content == "a"));
+
+        outbox.complete(entry.id, Some((local, "memos/2")));
+        assert!(matches!(
+            &outbox.front().unwrap().op,
+            OutboxOp::Update { memo_name, content: Some(content), .. }
+                if memo_name == "memos/2" && content == "b"
+        ));
+        assert!(outbox.is_deleted("memos/2"));
+
+        // A failed attempt is no longer in flight.
+        let entry = outbox.begin().unwrap();
+        outbox.retry_later(entry.id, "offline".to_string());
+        assert!(!outbox.front().unwrap().in_flight);
+    }
+
+    #[test]
+    fn test_outbox_conflict_keeps_other_ops() {
+        let outbox = Outbox::default();
+        let memo_name = "memos/1";
+        outbox.push(update(memo_name, "a"));
+        outbox.push(OutboxOp::Update {
+            memo_name: memo_name.to_string(),
+            content: None,
+            visibility: Some("PUBLIC".to_string()),
+        });
+        outbox.push(OutboxOp::Pin {
+            memo_name: memo_name.to_string(),
+            pinned: true,
+        });
+        outbox.push(update("memos/2", "b"));
+
+        outbox.add_conflict(MemoConflict {
+            memo_name: memo_name.to_string(),
+            base: MemoBase::default(),
+            local: Memo::default(),
+            remote: Memo::default(),
+            detected_at: Utc::now(),
+            pending: Vec::new(),
+        });
+
+        let data = outbox.data.read().clone();
+        assert_eq!(data.entries.len(), 1);
+        assert_eq!(data.entries[0].op.memo_name(), "memos/2");
+        let pending = &data.conflicts[0].pending;
+        assert_eq!(pending.len(), 2);
+        assert!(matches!(
+            &pending[0],
+            OutboxOp::Update { content: None, visibility: Some(x), .. } if x == "PUBLIC"
+        ));
+        assert!(matches!(&pending[1], OutboxOp::Pin { pinned: true, .. }));
+    }
+}
@@ -0,0 +1,197 @@
+use std::sync::Arc;
+
+use dashmap::DashMap;
+use serde::Serialize;
+use tauri::{AppHandle, Manager, State};
+
+use crate::{
+    store::{
+        memo::{flush_memo_cache, get_profile_id},
+        model::{AppState, CacheData, Profile},
+        outbox::{Outbox, load_outbox},
+        save_store_data,
+    },
+    sync::{self, SERVER_VERSION_KEY, SyncState, auth::AuthState, shortcut::SHORTCUTS_KEY},
+};
+
+/// Keys of `StoreData::data` that belong to an account, everything else is an
+/// app wide setting shared by all profiles.
+pub const PROFILE_KEYS: &[&str] = &[
+    "isAuthenticated",
+    "user",
+    "userName",
+    "serverUrl",
+    "accessToken",
+    SERVER_VERSION_KEY,
+    SHORTCUTS_KEY,
+    "lastEditText",
+    "lastEditVisibility",
+];
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProfileInfo {
+    pub id: String,
+    pub server_url: String,
+    pub user_name: String,
+    pub active: bool,
+}
+
+#[tauri::command]
+pub async fn list_profiles(state: State<'_, AppState>) -> Result<Vec<ProfileInfo>, String> {
+    log::trace!("[list_profiles]");
+
+    let mut profiles = Vec::new();
+    let server_url = state.store.server_url.read().clone();
+    let user_name = state.store.user_name.read().clone();
+    if !server_url.is_empty() && !user_name.is_empty() {
+        profiles.push(ProfileInfo {
+            id: get_profile_id(&server_url, &user_name),
+            server_url,
+            user_name,
+            active: true,
+        });
+    }
+
+    profiles.extend(state.store.profiles.read().iter().map(|x| ProfileInfo {
+        id: x.id.clone(),
+        server_url: x.server_url.clone(),
+        user_name: x.user_name.clone(),
+        active: false,
+    }));
+
+    Ok(profiles)
+}
+
+/// Parks the active account and activates `profile_id`. Without a profile id
+/// no account is active afterwards and the frontend shows the login page, the
+/// next login becomes a new profile.
+#[tauri::command]
+pub async fn switch_profile(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    sync_state: State<'_, SyncState>,
+    profile_id: Option<String>,
+) -> Result<(), String> {
+    log::info!("[switch_profile] profile: {:?}", profile_id);
+
+    // No sync pass may write one account's memos into the other's cache.
+    let _guard = sync_state.lock.lock().await;
+
+    let target = match &profile_id {
+        Some(id) => {
+            let profiles = state.store.profiles.read();
+            let profile = profiles
+                .iter()
+                .find(|x| &x.id == id)
+                .ok_or_else(|| format!("Profile not found: {}", id))?;
+            Some(profile.clone())
+        }
+        None => None,
+    };
+
+    let (cache, outbox) = match &target {
+        Some(profile) => load_account(&app, &state, &profile.server_url, &profile.user_name),
+        None => Default::default(),
+    };
+
+    let old_server_url = state.store.server_url.read().clone();
+    let old_user_name = state.store.user_name.read().clone();
+    let old_account = state.account();
+
+    let (old_cache, old_outbox) = {
+        let mut cache_lock = state.cache.write();
+        let mut outbox_lock = state.outbox.write();
+        let mut profiles = state.store.profiles.write();
+
+        if !old_server_url.is_empty() && !old_user_name.is_empty() {
+            let id = get_profile_id(&old_server_url, &old_user_name);
+            let data = PROFILE_KEYS
+                .iter()
+                .filter_map(|k| state.store.data.get(*k).map(|v| (k.to_string(), v.clone())))
+                .collect();
+            profiles.retain(|x| x.id != id);
+            profiles.push(Profile {
+                id,
+                server_url: old_server_url.clone(),
+                user_name: old_user_name.clone(),
+                data,
+            });
+        }
+
+        for key in PROFILE_KEYS {
+            state.store.data.remove(*key);
+        }
+        match &target {
+            Some(profile) => {
+                profiles.retain(|x| x.id != profile.id);
+                for (k, v) in profile.data.iter() {
+                    state.store.data.insert(k.clone(), v.clone());
+                }
+                *state.store.server_url.write() = profile.server_url.clone();
+                *state.store.user_name.write() = profile.user_name.clone();
+            }
+            None => {
+                *state.store.server_url.write() = "".to_string();
+                *state.store.user_name.write() = "".to_string();
+            }
+        }
+
+        (
+            std::mem::replace(&mut *cache_lock, Arc::new(cache)),
+            std::mem::replace(&mut *outbox_lock, Arc::new(outbox)),
+        )
+    };
+
+    // Nothing reaches the old cache anymore, write out what is left of it.
+    if let Ok(old_account) = old_account {
+        flush_memo_cache(&*state.memo_store, &old_account, &old_cache);
+    }
+    // Wakes the replay loop, which then picks up the new outbox.
+    app.state::<AuthState>().reset();
+    old_outbox.notify.notify_one();
+
+    save_store_data(&state).await?;
+
+    if target.is_some() {
+        sync::spawn_sync(app.clone());
+    }
+
+    Ok(())
+}
+
+/// Forgets a parked profile. The active one is removed by `logout`.
+#[tauri::command]
+pub async fn remove_profile(state: State<'_, AppState>, profile_id: String) -> Result<(), String> {
+    log::info!("[remove_profile] profile: {}", profile_id);
+
+    state.store.profiles.write().retain(|x| x.id != profile_id);
+    save_store_data(&state).await
+}
+
+/// Reads the cached memo metadata and the outbox of an account.
+pub fn load_account(
+    app: &AppHandle,
+    state: &AppState,
+    server_url: &str,
+    user_name: &str,
+) -> (CacheData, Outbox) {
+    let memo_meta = state
+        .memo_store
+        .load_meta(&get_profile_id(server_url, user_name))
+        .unwrap_or_else(|e| {
+            log::error!("Failed to load memo data: {}, using default", e);
+            state.load_errors.write().push(e);
+            DashMap::new()
+        });
+    let outbox = load_outbox(app, server_url, user_name).unwrap_or_else(|e| {
+        log::error!("Failed to load outbox: {}, using default", e);
+        state.load_errors.write().push(e);
+        Default::default()
+    });
+
+    (
+        CacheData::new(state.store.memo_cache_limits(), memo_meta),
+        outbox,
+    )
+}
@@ -0,0 +1,173 @@
+use std::path::{Path, PathBuf};
+
+use serde::{Serialize, de::DeserializeOwned};
+use serde_json::Value;
+
+use crate::utils::file::{read_file_with, write_file};
+
+/// Upgrades a value from one schema version to the next.
+pub type Migration = fn(&mut Value) -> Result<(), String>;
+
+/// Layout of one kind of persisted data. `migrations[i]` upgrades version
+/// `i` to `i + 1`, so the current version is the number of migrations.
+/// Data written before versioning is version 0.
+#[derive(Debug)]
+pub struct Schema {
+    pub name: &'static str,
+    pub migrations: &'static [Migration],
+}
+
+/// Version 0 data has the same layout as version 1, only the header is new.
+fn add_header(_: &mut Value) -> Result<(), String> {
+    Ok(())
+}
+
+pub const STORE: Schema = Schema {
+    name: "store",
+    migrations: &[add_header],
+};
+
+pub const SECRETS: Schema = Schema {
+    name: "secrets",
+    migrations: &[add_header],
+};
+
+pub const OUTBOX: Schema = Schema {
+    name: "outbox",
+    migrations: &[add_header],
+};
+
+pub const SYNC_META: Schema = Schema {
+    name: "sync meta",
+    migrations: &[add_header],
+};
+
+/// Memos kept as JSON in the memo database.
+pub const MEMO: Schema = Schema {
+    name: "memo",
+    migrations: &[add_header],
+};
+
+impl Schema {
+    pub fn version(&self) -> u32 {
+        self.migrations.len() as u32
+    }
+
+    /// Brings `value` from `version` up to the current version.
+    pub fn migrate(&self, value: &mut Value, version: u32) -> Result<(), String> {
+        if version > self.version() {
+            return Err(format!(
+                "{} version {} is newer than the supported {}",
+                self.name,
+                version,
+                self.version()
+            ));
+        }
+
+        for (i, migration) in self.migrations.iter().enumerate().skip(version as usize) {
+            migration(value).map_err(|e| {
+                format!(
+                    "Failed to migrate {} to version {}: {}",
+                    self.name,
+                    i + 1,
+                    e
+                )
+            })?;
+        }
+        Ok(())
+    }
+
+    pub fn parse<T: DeserializeOwned>(&self, version: u32, content: &str) -> Result<T, String> {
+        let mut value = serde_json::from_str(content)
+            .map_err(|e| format!("Failed to parse {}: {}", self.name, e))?;
+        self.migrate(&mut value, version)?;
+        serde_json::from_value(value).map_err(|e| format!("Failed to parse {}: {}", self.name, e))
+    }
+
+    /// Reads a file written by `write` and the version it had, upgraded in
+    /// memory only.
+    pub fn read_versioned<T: DeserializeOwned>(
+        &self,
+        path: &Path,
+    ) -> Result<Option<(u32, T)>, String> {
+        read_file_with(path, self.version(), |version, content| {
+            Ok((version, self.parse::<T>(version, content)?))
+        })
+    }
+
+    /// Reads a file written by `write`, upgrading it in place if it has an
+    /// older version.
+    pub fn read<T: DeserializeOwned + Serialize>(&self, path: &Path) -> Result<Option<T>, String> {
+        let Some((version, data)) = self.read_versioned::<T>(path)? else {
+            return Ok(None);
+        };
+
+        if version < self.version() {
+            log::info!(
+                "Upgrading {} from version {} to {}",
+                self.name,
+                version,
+                self.version()
+            );
+            self.write(path, &data)?;
+        }
+
+        Ok(Some(data))
+    }
+
+    /// `read` on the blocking pool.
+    pub async fn read_async<T>(&'static self, path: PathBuf) -> Result<Option<T>, String>
+    where
+        T: DeserializeOwned + Serialize + Send + 'static,
+    {
+        tokio::task::spawn_blocking(move || self.read(&path))
+            .await
+            .map_err(|e| format!("Failed to read {}: {}", self.name, e))?
+    }
+
+    pub fn write<T: Serialize>(&self, path: &Path, data: &T) -> Result<(), String> {
+        let content = serde_json::to_string(data)
+            .map_err(|e| format!("Failed to serialize {}: {}", self.name, e))?;
+        self.write_str(path, &content)
+    }
+
+    pub fn write_str(&self, path: &Path, content: &str) -> Result<(), String> {
+        write_file(path, content, self.version())
+    }
+
+    /// `write_str` on the blocking pool, the write waits for fsync.
+    pub async fn write_str_async(&self, path: PathBuf, content: String) -> Result<(), String> {
+        let version = self.version();
+        tokio::task::spawn_blocking(move || write_file(&path, &content, version))
+            .await
+            .map_err(|e| format!("Failed to write {}: {}", self.name, e))?
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rename_title(value: &mut Value) -> Result<(), String> {
+        let object = value.as_object_mut().ok_or("Not an object")?;
+        let title = object.remove("title").unwrap_or_default();
+        object.insert("content".to_string(), title);
+        Ok(())
+    }
+
+    const TEST: Schema = Schema {
+        name: "test",
+        migrations: &[add_header, rename_title],
+    };
+
+    #[test]
+    fn test_migrate() {
+        let v: Value = TEST.parse(0, r#"{"title":"a"}"#).unwrap();
+        assert_eq!(v, serde_json::json!({"content": "a"}));
+
+        let v: Value = TEST.parse(2, r#"{"content":"b"}"#).unwrap();
+        assert_eq!(v, serde_json::json!({"content": "b"}));
+
+        assert!(TEST.parse::<Value>(3, "{}").is_err());
+    }
+}
@@ -0,0 +1,875 @@
+use std::collections::{HashMap, HashSet};
+
+use regex::RegexBuilder;
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, State};
+
+use crate::{
+    store::{
+        filter::MemoFilter,
+        index::{ListFilter, MemoSort},
+        memo::{load_memos, peek_memos},
+        model::{AppState, Memo},
+        with_state_blocking,
+    },
+    utils::zh,
+};
+
+/// Version of `tokenize`, memos indexed by another version are indexed again
+/// when the memo store opens.
+pub const INDEX_VERSION: u32 = 2;
+
+/// Prefix of pinyin terms, `~zhong` for `中` and `~zhong~wen` for `中文`.
+const PINYIN_PREFIX: char = '~';
+/// Prefix of the pinyin initials of character pairs, `^zw` for `中文`.
+const INITIALS_PREFIX: char = '^';
+
+/// Whether `term` is a word or CJK term, not pinyin.
+pub fn is_text_term(term: &str) -> bool {
+    !term.starts_with([PINYIN_PREFIX, INITIALS_PREFIX])
+}
+
+/// A term of a text and where it is, in bytes.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Token {
+    pub term: String,
+    pub start: usize,
+    pub end: usize,
+}
+
+/// Han, kana and Hangul, which are written without spaces between words.
+fn is_cjk(c: char) -> bool {
+    matches!(
+        c as u32,
+        0x3040..=0x30FF
+            | 0x3400..=0x4DBF
+            | 0x4E00..=0x9FFF
+            | 0xAC00..=0xD7AF
+            | 0xF900..=0xFAFF
+            | 0x20000..=0x2FA1F
+    )
+}
+
+fn initial(syllable: &str) -> &str {
+    &syllable[..1]
+}
+
+/// Splits text into lowercase words, and CJK runs into single characters and
+/// overlapping pairs so words of any length are found without a dictionary.
+/// Han characters are simplified and also give their pinyin terms.
+pub fn tokenize(text: &str) -> Vec<Token> {
+    let mut tokens = Vec::new();
+    let mut word: Option<usize> = None;
+    let mut prev_cjk: Option<(usize, char, Option<&str>)> = None;
+
+    for (i, c) in text
+        .char_indices()
+        .chain(std::iter::once((text.len(), ' ')))
+    {
+        if c.is_alphanumeric() && !is_cjk(c) {
+            word.get_or_insert(i);
+        } else if let Some(start) = word.take() {
+            tokens.push(Token {
+                term: text[start..i].to_lowercase(),
+                start,
+                end: i,
+            });
+        }
+
+        if !is_cjk(c) {
+            prev_cjk = None;
+            continue;
+        }
+
+        let end = i + c.len_utf8();
+        let c = zh::simplify(c);
+        let syllable = zh::pinyin(c);
+        let mut push = |term, start| tokens.push(Token { term, start, end });
+        push(c.to_string(), i);
+        if let Some(syllable) = syllable {
+            push(format!("{}{}", PINYIN_PREFIX, syllable), i);
+        }
+        if let Some((start, prev, prev_syllable)) = prev_cjk {
+            push(format!("{}{}", prev, c), start);
+            if let (Some(a), Some(b)) = (prev_syllable, syllable) {
+                push(format!("{0}{1}{0}{2}", PINYIN_PREFIX, a, b), start);
+                push(
+                    format!("{}{}{}", INITIALS_PREFIX, initial(a), initial(b)),
+                    start,
+                );
+            }
+        }
+        prev_cjk = Some((i, c, syllable));
+    }
+    tokens
+}
+
+/// One word of a query, matched by any of its alternatives. An alternative
+/// is a list of terms that all have to occur.
+pub type QueryPart = Vec<Vec<String>>;
+
+/// Parses a search query. CJK runs longer than one character only need
+/// their pairs, Latin words also match as pinyin or pinyin initials.
+pub fn parse_query(query: &str) -> Vec<QueryPart> {
+    let tokens = tokenize(query)
+        .into_iter()
+        .filter(|x| is_text_term(&x.term))
+        .collect::<Vec<_>>();
+
+    let mut parts = Vec::new();
+    for (i, token) in tokens.iter().enumerate() {
+        let is_char = token.term.chars().count() == 1 && token.term.chars().all(is_cjk);
+        // A pair starting or ending at this character covers it.
+        let covered = is_char
+            && tokens.iter().enumerate().any(|(j, x)| {
+                j != i
+                    && x.term.chars().count() == 2
+                    && x.start <= token.start
+                    && x.end >= token.end
+            });
+        if covered {
+            continue;
+        }
+
+        let mut part = vec![vec![token.term.clone()]];
+        if token.term.chars().all(|c| c.is_ascii_lowercase()) {
+            part.extend(pinyin_terms(&token.term));
+            part.extend(initials_terms(&token.term));
+        }
+        if !parts.contains(&part) {
+            parts.push(part);
+        }
+    }
+    parts
+}
+
+/// All terms of a query, for loading their postings.
+pub fn query_terms(query: &[QueryPart]) -> Vec<String> {
+    let mut terms = Vec::new();
+    for term in query.iter().flatten().flatten() {
+        if !terms.contains(term) {
+            terms.push(term.clone());
+        }
+    }
+    terms
+}
+
+/// `zhongwen` as `~zhong~wen`, if it is a run of syllables.
+fn pinyin_terms(word: &str) -> Option<Vec<String>> {
+    let syllables = split_syllables(word)?;
+    Some(match syllables.as_slice() {
+        [x] => vec![format!("{}{}", PINYIN_PREFIX, x)],
+        _ => syllables
+            .windows(2)
+            .map(|x| format!("{0}{1}{0}{2}", PINYIN_PREFIX, x[0], x[1]))
+            .collect(),
+    })
+}
+
+/// `zw` as the initials of the pairs of a run of characters.
+fn initials_terms(word: &str) -> Option<Vec<String>> {
+    let chars = word.chars().collect::<Vec<_>>();
+    if chars.len() < 2 {
+        return None;
+    }
+    Some(
+        chars
+            .windows(2)
+            .map(|x| format!("{}{}{}", INITIALS_PREFIX, x[0], x[1]))
+            .collect(),
+    )
+}
+
+/// Splits a lowercase ASCII word into pinyin syllables, longest first.
+fn split_syllables(word: &str) -> Option<Vec<&str>> {
+    // Syllables have at most six letters, like `zhuang`.
+    const MAX_SYLLABLE: usize = 6;
+    const MAX_WORD: usize = 36;
+
+    if word.is_empty() {
+        return Some(Vec::new());
+    }
+    if word.len() > MAX_WORD {
+        return None;
+    }
+    (1..=word.len().min(MAX_SYLLABLE)).rev().find_map(|n| {
+        let (head, tail) = word.split_at(n);
+        if !zh::is_syllable(head) {
+            return None;
+        }
+        let mut syllables = split_syllables(tail)?;
+        syllables.insert(0, head);
+        Some(syllables)
+    })
+}
+
+/// How often each term occurs in `text`.
+pub fn term_counts(text: &str) -> HashMap<String, u32> {
+    let mut counts = HashMap::new();
+    for token in tokenize(text) {
+        *counts.entry(token.term).or_insert(0) += 1;
+    }
+    counts
+}
+
+/// Case and Traditional characters folded, for comparing text.
+pub fn fold(text: &str) -> String {
+    text.chars()
+        .flat_map(|c| zh::simplify(c).to_lowercase())
+        .collect()
+}
+
+/// Whether `query` occurs in `text`, in its pinyin or in its pinyin
+/// initials, ignoring case and Traditional characters.
+pub fn text_matches(text: &str, query: &str) -> bool {
+    let (text, query) = (fold(text), fold(query));
+    if text.contains(&query) {
+        return true;
+    }
+    if query.is_empty() || !query.chars().all(|c| c.is_ascii_alphanumeric()) {
+        return false;
+    }
+
+    let mut full = String::new();
+    let mut initials = String::new();
+    for c in text.chars() {
+        match zh::pinyin(c) {
+            Some(syllable) => {
+                full.push_str(syllable);
+                initials.push_str(initial(syllable));
+            }
+            None => {
+                full.push(c);
+                initials.push(c);
+            }
+        }
+    }
+    full.contains(&query) || initials.contains(&query)
+}
+
+/// Typos a fuzzy match of `word` may have, none for words shorter than
+/// three characters.
+fn max_typos(word: &str) -> usize {
+    match word.chars().count() {
+        0..=2 => 0,
+        3..=5 => 1,
+        _ => 2,
+    }
+}
+
+/// Edits turning `a` into `b`, a swap of neighbours counting as one, or
+/// `None` when there are more than `max`.
+pub fn edit_distance(a: &str, b: &str, max: usize) -> Option<usize> {
+    let a = a.chars().collect::<Vec<_>>();
+    let b = b.chars().collect::<Vec<_>>();
+    if a.len().abs_diff(b.len()) > max {
+        return None;
+    }
+
+    let mut before = Vec::new();
+    let mut prev = (0..=b.len()).collect::<Vec<_>>();
+    for i in 1..=a.len() {
+        let mut row = vec![i; b.len() + 1];
+        for j in 1..=b.len() {
+            let cost = usize::from(a[i - 1] != b[j - 1]);
+            row[j] = (prev[j] + 1).min(row[j - 1] + 1).min(prev[j - 1] + cost);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                row[j] = row[j].min(before[j - 2] + 1);
+            }
+        }
+        if row.iter().all(|x| *x > max) {
+            return None;
+        }
+        before = std::mem::replace(&mut prev, row);
+    }
+    Some(prev[b.len()]).filter(|x| *x <= max)
+}
+
+/// Character counts of the words of `query` that fuzzy search looks up.
+fn fuzzy_lengths(query: &[QueryPart]) -> Option<(usize, usize)> {
+    let lengths = query
+        .iter()
+        .map(|x| &x[0][0])
+        .filter(|x| max_typos(x) > 0 && !x.chars().any(is_cjk))
+        .map(|x| (x.chars().count(), max_typos(x)));
+    let min = lengths.clone().map(|(len, typos)| len - typos).min()?;
+    let max = lengths.map(|(len, typos)| len + typos).max()?;
+    Some((min, max))
+}
+
+/// Adds the terms of `vocabulary` within a few typos of a Latin word of
+/// `query` as alternatives of its part, closest first.
+pub fn add_fuzzy_terms(query: &mut [QueryPart], vocabulary: &HashSet<String>) {
+    for part in query {
+        let word = part[0][0].clone();
+        let max = max_typos(&word);
+        if max == 0 || word.chars().any(is_cjk) {
+            continue;
+        }
+        let mut similar = vocabulary
+            .iter()
+            .filter(|x| **x != word)
+            .filter_map(|x| Some((edit_distance(&word, x, max)?, x)))
+            .collect::<Vec<_>>();
+        similar.sort();
+        part.extend(similar.into_iter().map(|(_, x)| vec![x.clone()]));
+    }
+}
+
+/// What the memo store knows about some terms, enough to rank memos.
+#[derive(Debug, Default)]
+pub struct Postings {
+    pub memo_count: usize,
+    /// Terms of all memos, for the average memo length.
+    pub total_terms: u64,
+    /// Term count of the memos in `terms`.
+    pub lengths: HashMap<String, u32>,
+    /// Memo name to term frequency, per term.
+    pub terms: HashMap<String, HashMap<String, u32>>,
+}
+
+impl Postings {
+    /// Replaces what is known about `memo` for the query `terms`, for
+    /// changes not written yet. `stored` is the written version, if any.
+    pub fn update(&mut self, memo: &Memo, stored: Option<&Memo>, terms: &[String]) {
+        let counts = term_counts(&memo.content);
+        for term in terms {
+            let memos = self.terms.entry(term.clone()).or_default();
+            match counts.get(term) {
+                Some(count) => memos.insert(memo.name.clone(), *count),
+                None => memos.remove(&memo.name),
+            };
+        }
+
+        let length = counts.values().sum::<u32>();
+        match stored {
+            Some(x) => {
+                let stored_length = term_counts(&x.content).values().sum::<u32>();
+                self.total_terms = self.total_terms.saturating_sub(stored_length as u64);
+            }
+            None => self.memo_count += 1,
+        }
+        self.total_terms += length as u64;
+        self.lengths.insert(memo.name.clone(), length);
+    }
+
+    /// BM25 scores of the memos matching every part of `query`, best first.
+    /// A part scores by its best matching alternative.
+    pub fn rank(&self, query: &[QueryPart]) -> Vec<(String, f64)> {
+        const K1: f64 = 1.2;
+        const B: f64 = 0.75;
+
+        let Some(first) = query.first() else {
+            return Vec::new();
+        };
+        let memo_count = self.memo_count.max(1) as f64;
+        let avg_len = (self.total_terms as f64 / memo_count).max(1.0);
+        let score = |term: &String, name: &String, len: f64| {
+            let memos = self.terms.get(term)?;
+            let tf = *memos.get(name)? as f64;
+            let df = memos.len() as f64;
+            let idf = (1.0 + (memo_count - df + 0.5) / (df + 0.5)).ln();
+            Some(idf * tf * (K1 + 1.0) / (tf + K1 * (1.0 - B + B * len / avg_len)))
+        };
+
+        let candidates = first
+            .iter()
+            .filter_map(|x| self.terms.get(x.first()?))
+            .flat_map(|x| x.keys())
+            .collect::<HashSet<_>>();
+        let mut ranked = candidates
+            .into_iter()
+            .filter_map(|name| {
+                let len = self.lengths.get(name).copied().unwrap_or_default() as f64;
+                let mut total = 0.0;
+                for part in query {
+                    total += part
+                        .iter()
+                        .filter_map(|x| x.iter().map(|term| score(term, name, len)).sum())
+                        .max_by(f64::total_cmp)?;
+                }
+                Some((name.clone(), total))
+            })
+            .collect::<Vec<_>>();
+        ranked.sort_by(|a, b| b.1.total_cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        ranked
+    }
+}
+
+/// Characters of context before the first match.
+const SNIPPET_BEFORE: usize = 30;
+const SNIPPET_LEN: usize = 120;
+
+/// Part of a memo around its first match.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Snippet {
+    pub text: String,
+    /// Start and end of each match in `text`, in UTF-16 code units as
+    /// JavaScript strings count them.
+    pub highlights: Vec<(usize, usize)>,
+}
+
+fn utf16_len(text: &str) -> usize {
+    text.chars().map(char::len_utf16).sum()
+}
+
+/// Byte ranges sorted, with overlapping ones merged.
+fn merge_ranges(ranges: &[(usize, usize)]) -> Vec<(usize, usize)> {
+    let mut ranges = ranges.to_vec();
+    ranges.sort();
+    let mut merged: Vec<(usize, usize)> = Vec::new();
+    for (start, end) in ranges {
+        match merged.last_mut() {
+            Some(last) if start <= last.1 => last.1 = last.1.max(end),
+            _ => merged.push((start, end)),
+        }
+    }
+    merged
+}
+
+/// Byte ranges of `text` in UTF-16 code units.
+fn utf16_ranges(text: &str, ranges: &[(usize, usize)]) -> Vec<(usize, usize)> {
+    ranges
+        .iter()
+        .map(|(x, y)| (utf16_len(&text[..*x]), utf16_len(&text[..*y])))
+        .collect()
+}
+
+/// A snippet highlighting the byte ranges `matches` of `content`.
+pub fn snippet_at(content: &str, matches: &[(usize, usize)]) -> Snippet {
+    let merged = merge_ranges(matches);
+    let first = merged.first().map_or(0, |x| x.0);
+    let start = content[..first]
+        .char_indices()
+        .rev()
+        .nth(SNIPPET_BEFORE - 1)
+        .map_or(0, |(i, _)| i);
+    let end = content[start..]
+        .char_indices()
+        .nth(SNIPPET_LEN)
+        .map_or(content.len(), |(i, _)| start + i);
+
+    let mut text = String::new();
+    if start > 0 {
+        text.push('…');
+    }
+    let offset = utf16_len(&text);
+    text.push_str(&content[start..end]);
+    if end < content.len() {
+        text.push('…');
+    }
+
+    let highlights = merged
+        .into_iter()
+        .filter(|x| x.0 >= start && x.1 <= end)
+        .map(|(x, y)| {
+            (
+                offset + utf16_len(&content[start..x]),
+                offset + utf16_len(&content[start..y]),
+            )
+        })
+        .collect();
+    Snippet { text, highlights }
+}
+
+/// Byte ranges of `content` where `terms` occur.
+fn term_matches(content: &str, terms: &[String]) -> Vec<(usize, usize)> {
+    let terms = terms.iter().collect::<HashSet<_>>();
+    tokenize(content)
+        .into_iter()
+        .filter(|x| terms.contains(&x.term))
+        .map(|x| (x.start, x.end))
+        .collect()
+}
+
+/// Regexes over this many bytes compiled are rejected.
+const REGEX_SIZE_LIMIT: usize = 1 << 20;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum SearchMode {
+    /// Indexed terms, ranked by BM25.
+    #[default]
+    Terms,
+    /// Like `Terms`, Latin words also matching terms with a typo or two.
+    Fuzzy,
+    /// `query` is a regex over the content, newest memos first.
+    Regex,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SearchRequest {
+    pub query: String,
+    #[serde(default)]
+    pub mode: SearchMode,
+    #[serde(default)]
+    pub filter: MemoFilter,
+    pub limit: usize,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SearchHit {
+    pub memo: Memo,
+    pub score: f64,
+    pub snippet: Snippet,
+    /// Start and end of each match in the content, in UTF-16 code units.
+    pub matches: Vec<(usize, usize)>,
+}
+
+impl SearchHit {
+    fn new(memo: Memo, score: f64, matches: &[(usize, usize)]) -> Self {
+        let merged = merge_ranges(matches);
+        Self {
+            snippet: snippet_at(&memo.content, &merged),
+            matches: utf16_ranges(&memo.content, &merged),
+            memo,
+            score,
+        }
+    }
+}
+
+/// Full-text search over the cached memos, best matches first. Works offline.
+#[tauri::command]
+pub async fn search_memos(
+    app: AppHandle,
+    request: SearchRequest,
+) -> Result<Vec<SearchHit>, String> {
+    log::trace!("[search_memos] request: {:?}", request);
+
+    // Index reads and scoring take a while on large caches.
+    with_state_blocking(&app, move |state| search(state, &request)).await
+}
+
+/// Tags of the cached memos matching `query` like `text_matches`, the most
+/// used first.
+#[tauri::command]
+pub async fn search_tags(state: State<'_, AppState>, query: String) -> Result<Vec<String>, String> {
+    log::trace!("[search_tags] query: {:?}", query);
+
+    let mut counts = HashMap::<String, usize>::new();
+    for x in state.cache().all_memo_meta.iter() {
+        for tag in &x.tags {
+            *counts.entry(tag.clone()).or_default() += 1;
+        }
+    }
+
+    let mut tags = counts
+        .into_iter()
+        .filter(|(tag, _)| text_matches(tag, &query))
+        .collect::<Vec<_>>();
+    tags.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    Ok(tags.into_iter().map(|(tag, _)| tag).collect())
+}
+
+pub fn search(state: &AppState, request: &SearchRequest) -> Result<Vec<SearchHit>, String> {
+    if request.limit == 0 {
+        return Ok(Vec::new());
+    }
+    match request.mode {
+        SearchMode::Terms | SearchMode::Fuzzy => search_terms(state, request),
+        SearchMode::Regex => search_regex(state, request),
+    }
+}
+
+fn search_terms(state: &AppState, request: &SearchRequest) -> Result<Vec<SearchHit>, String> {
+    let mut query = parse_query(&request.query);
+    if query.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let cache = state.cache();
+    let account = state.account()?;
+    if request.mode == SearchMode::Fuzzy
+        && let Some((min, max)) = fuzzy_lengths(&query)
+    {
+        let mut vocabulary = state
+            .memo_store
+            .load_terms(&account, min, max)?
+            .into_iter()
+            .collect::<HashSet<_>>();
+        for (_, memo, _) in cache.memos.dirty() {
+            vocabulary.extend(
+                term_counts(&memo.content)
+                    .into_keys()
+                    .filter(|x| is_text_term(x)),
+            );
+        }
+        add_fuzzy_terms(&mut query, &vocabulary);
+    }
+    let terms = query_terms(&query);
+
+    let mut postings = state.memo_store.load_postings(&account, &terms)?;
+    let dirty = cache.memos.dirty();
+    let names = dirty
+        .iter()
+        .map(|(name, _, _)| name.clone())
+        .collect::<Vec<_>>();
+    let stored = state.memo_store.load_memos(&account, &names)?;
+    for (name, memo, _) in &dirty {
+        postings.update(memo, stored.get(name), &terms);
+    }
+
+    let filter = &request.filter;
+    let ranked = postings
+        .rank(&query)
+        .into_iter()
+        .filter(|(name, _)| {
+            cache
+                .all_memo_meta
+                .get(name)
+                .is_some_and(|x| filter.matches_meta(&x))
+        })
+        .collect::<Vec<_>>();
+
+    let mut hits = Vec::new();
+    for batch in ranked.chunks(request.limit.max(16)) {
+        let names = batch
+            .iter()
+            .map(|(name, _)| name.clone())
+            .collect::<Vec<_>>();
+        let mut memos = load_memos(state, &names)?
+            .into_iter()
+            .map(|x| (x.name.clone(), x))
+            .collect::<HashMap<_, _>>();
+        for (name, score) in batch {
+            if let Some(memo) = memos.remove(name)
+                && (!filter.needs_memo() || filter.matches_memo(&memo))
+            {
+                let matches = term_matches(&memo.content, &terms);
+                hits.push(SearchHit::new(memo, *score, &matches));
+            }
+        }
+        if hits.len() >= request.limit {
+            break;
+        }
+    }
+    hits.truncate(request.limit);
+    Ok(hits)
+}
+
+/// Memos whose content matches the regex `request.query`, in the order of
+/// `get_memo_list`. The score is the number of matches.
+fn search_regex(state: &AppState, request: &SearchRequest) -> Result<Vec<SearchHit>, String> {
+    let regex = RegexBuilder::new(&request.query)
+        .size_limit(REGEX_SIZE_LIMIT)
+        .build()
+        .map_err(|e| format!("Failed to parse regex: {}", e))?;
+
+    let cache = state.cache();
+    let filter = &request.filter;
+    let list = ListFilter {
+        tag: filter
+            .tags
+            .as_ref()
+            .and_then(|x| x.required_tag())
+            .unwrap_or_default(),
+        pinned: filter.pinned,
+        state: filter.state(),
+    };
+    let names = cache
+        .index
+        .read()
+        .scan(list, MemoSort::default(), None)
+        .filter(|(_, name)| {
+            cache
+                .all_memo_meta
+                .get(name)
+                .is_some_and(|x| filter.matches_meta(&x))
+        })
+        .map(|(_, name)| name.clone())
+        .collect::<Vec<_>>();
+
+    let mut hits = Vec::new();
+    for batch in names.chunks(request.limit.max(16)) {
+        let mut memos = peek_memos(state, batch)?;
+        for name in batch {
+            let Some(memo) = memos.remove(name) else {
+                continue;
+            };
+            if filter.needs_memo() && !filter.matches_memo(&memo) {
+                continue;
+            }
+            // Empty matches, like those of `a*`, would match every memo.
+            let matches = regex
+                .find_iter(&memo.content)
+                .filter(|x| !x.is_empty())
+                .map(|x| (x.start(), x.end()))
+                .collect::<Vec<_>>();
+            if !matches.is_empty() {
+                hits.push(SearchHit::new(memo, matches.len() as f64, &matches));
+            }
+            if hits.len() >= request.limit {
+                return Ok(hits);
+            }
+        }
+    }
+    Ok(hits)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        api::models::V1State,
+        store::memo::{cache_memo, flush_active_cache},
+    };
+
+    #[test]
+    fn test_tokenize() {
+        let terms = |text| {
+            tokenize(text)
+                .into_iter()
+                .map(|x| x.term)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(terms("Hello, wörld 42"), ["hello", "wörld", "42"]);
+        assert_eq!(
+            terms("學中ok"),
+            [
+                "学",
+                "~xue",
+                "中",
+                "~zhong",
+                "学中",
+                "~xue~zhong",
+                "^xz",
+                "ok"
+            ]
+        );
+        assert_eq!(
+            query_terms(&parse_query("学中文 学")),
+            ["学中", "中文", "学"]
+        );
+        assert_eq!(
+            parse_query("xuezw")[0],
+            [
+                vec!["xuezw".to_string()],
+                vec![
+                    "^xu".to_string(),
+                    "^ue".to_string(),
+                    "^ez".to_string(),
+                    "^zw".to_string()
+                ],
+            ]
+        );
+        assert_eq!(
+            query_terms(&parse_query("zhongwen"))[1..3],
+            ["~zhong~wen", "^zh"]
+        );
+    }
+
+    #[test]
+    fn test_text_matches() {
+        assert!(text_matches("工作/會議", "会议"));
+        assert!(text_matches("工作/會議", "huiyi"));
+        assert!(text_matches("工作/會議", "gz"));
+        assert!(text_matches("Work", "wo"));
+        assert!(!text_matches("工作", "gzh"));
+    }
+
+    #[test]
+    fn test_snippet() {
+        let content = format!("{}今天学习 Rust", "x ".repeat(40));
+        let terms = query_terms(&parse_query("學習 rust"));
+        let snippet = snippet_at(&content, &term_matches(&content, &terms));
+        assert!(snippet.text.starts_with('…'));
+        let text = snippet.text.encode_utf16().collect::<Vec<_>>();
+        let highlighted = snippet
+            .highlights
+            .iter()
+            .map(|(x, y)| String::from_utf16(&text[*x..*y]).unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(highlighted, ["学习", "Rust"]);
+    }
+
+    #[test]
+    fn test_search() {
+        let state = AppState::in_memory();
+
+        for (name, content) in [
+            ("memos/1", "买牛奶 and bread"),
+            ("memos/2", "牛奶 牛奶 milk"),
+            ("memos/3", "奶牛"),
+        ] {
+            let memo = Memo {
+                name: name.to_string(),
+                content: content.to_string(),
+                state: V1State::Normal,
+                ..Default::default()
+            };
+            cache_memo(&state, name.to_string(), memo);
+        }
+        flush_active_cache(&state);
+
+        let mut request = SearchRequest {
+            query: "牛奶".to_string(),
+            mode: SearchMode::Terms,
+            filter: MemoFilter::default(),
+            limit: 10,
+        };
+        let names =
+            |hits: Vec<SearchHit>| hits.into_iter().map(|x| x.memo.name).collect::<Vec<_>>();
+        assert_eq!(
+            names(search(&state, &request).unwrap()),
+            ["memos/2", "memos/1"]
+        );
+
+        request.query = "niunai".to_string();
+        assert_eq!(
+            names(search(&state, &request).unwrap()),
+            ["memos/2", "memos/1"]
+        );
+        request.query = "nn".to_string();
+        assert_eq!(search(&state, &request).unwrap().len(), 3);
+
+        // Unwritten changes are searched too.
+        let mut memo = state.cache().memos.get("memos/3").unwrap();
+        memo.content = "bread".to_string();
+        cache_memo(&state, "memos/3".to_string(), memo);
+        request.query = "BREAD".to_string();
+        assert_eq!(
+            names(search(&state, &request).unwrap()),
+            ["memos/3", "memos/1"]
+        );
+        let memo = Memo {
+            name: "memos/4".to_string(),
+            content: "toast".to_string(),
+            state: V1State::Normal,
+            ..Default::default()
+        };
+        cache_memo(&state, "memos/4".to_string(), memo);
+        request.query = "toast".to_string();
+        assert_eq!(names(search(&state, &request).unwrap()), ["memos/4"]);
+
+        request.mode = SearchMode::Fuzzy;
+        request.query = "mlik".to_string();
+        assert_eq!(names(search(&state, &request).unwrap()), ["memos/2"]);
+        request.query = "bred 买".to_string();
+        assert_eq!(names(search(&state, &request).unwrap()), ["memos/1"]);
+        request.query = "mi".to_string();
+        assert!(search(&state, &request).unwrap().is_empty());
+
+        request.mode = SearchMode::Regex;
+        request.query = r"\b[a-z]{3,5}\b".to_string();
+        let hits = search(&state, &request).unwrap();
+        let hit = hits.iter().find(|x| x.memo.name == "memos/1").unwrap();
+        assert_eq!(hit.matches, [(4, 7), (8, 13)]);
+        assert_eq!(hit.score, 2.0);
+        request.filter.archived = true;
+        assert!(search(&state, &request).unwrap().is_empty());
+        request.query = "(".to_string();
+        assert!(search(&state, &request).is_err());
+    }
+
+    #[test]
+    fn test_edit_distance() {
+        assert_eq!(edit_distance("milk", "milk", 1), Some(0));
+        assert_eq!(edit_distance("mlik", "milk", 1), Some(1));
+        assert_eq!(edit_distance("bred", "bread", 1), Some(1));
+        assert_eq!(edit_distance("kitten", "sitting", 2), None);
+        assert_eq!(edit_distance("kitten", "sitting", 3), Some(3));
+    }
+}
@@ -0,0 +1,228 @@
+use std::{collections::HashMap, path::Path};
+
+use base64::{Engine, engine::general_purpose::STANDARD};
+use chacha20poly1305::{
+    ChaCha20Poly1305, Key, Nonce,
+    aead::{Aead, AeadCore, KeyInit, OsRng, Payload},
+};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::store::model::StoreData;
+
+/// Keys of `StoreData::data` that never reach `store.json` in plaintext.
+pub const SECRET_KEYS: &[&str] = &["accessToken"];
+
+const KEY_FILE: &str = "secret.key";
+const NONCE_LEN: usize = 12;
+const SECRET_DATA_VERSION: u32 = 1;
+
+/// Sealed secrets, stored in `secrets.json` next to `store.json`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SecretData {
+    pub version: u32,
+    /// Entry name to base64 of nonce and ciphertext.
+    pub entries: HashMap<String, String>,
+}
+
+/// Authenticated encryption (ChaCha20-Poly1305) of store secrets. The key is
+/// kept in its own file readable by the current user only, so a copied or
+/// synced `store.json` is useless on its own.
+pub struct SecretBox {
+    cipher: ChaCha20Poly1305,
+}
+
+impl SecretBox {
+    /// Reads the key from `dir`, creating one on first use. An invalid key
+    /// file is replaced too, the secrets sealed with the lost key no longer
+    /// open and the user has to log in again.
+    pub fn load_or_create(dir: &Path) -> Result<Self, String> {
+        let path = dir.join(KEY_FILE);
+
+        match std::fs::read(&path) {
+            Ok(key) if key.len() == 32 => {
+                return Ok(Self {
+                    cipher: ChaCha20Poly1305::new(Key::from_slice(&key)),
+                });
+            }
+            Ok(_) => log::error!(
+                "Invalid secret key file, creating a new one: {}",
+                path.display()
+            ),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+            Err(e) => return Err(format!("Failed to read secret key: {}", e)),
+        }
+
+        let key = ChaCha20Poly1305::generate_key(&mut OsRng);
+        write_key_file(&path, &key)?;
+        Ok(Self {
+            cipher: ChaCha20Poly1305::new(&key),
+        })
+    }
+
+    /// `name` is authenticated with the value, a sealed value copied to another
+    /// entry fails to open.
+    pub fn seal(&self, name: &str, value: &str) -> Result<String, String> {
+        let nonce = ChaCha20Poly1305::generate_nonce(&mut OsRng);
+        let payload = Payload {
+            msg: value.as_bytes(),
+            aad: name.as_bytes(),
+        };
+        let sealed = self
+            .cipher
+            .encrypt(&nonce, payload)
+            .map_err(|e| format!("Failed to encrypt secret: {}", e))?;
+
+        let mut data = nonce.to_vec();
+        data.extend(sealed);
+        Ok(STANDARD.encode(data))
+    }
+
+    pub fn open(&self, name: &str, sealed: &str) -> Result<String, String> {
+        let data = STANDARD
+            .decode(sealed)
+            .map_err(|e| format!("Failed to decode secret: {}", e))?;
+        if data.len() < NONCE_LEN {
+            return Err("Failed to decode secret: too short".to_string());
+        }
+
+        let (nonce, sealed) = data.split_at(NONCE_LEN);
+        let payload = Payload {
+            msg: sealed,
+            aad: name.as_bytes(),
+        };
+        let value = self
+            .cipher
+            .decrypt(Nonce::from_slice(nonce), payload)
+            .map_err(|e| format!("Failed to decrypt secret: {}", e))?;
+
+        String::from_utf8(value).map_err(|e| format!("Failed to decode secret: {}", e))
+    }
+}
+
+/// Moves `SECRET_KEYS` out of serialized store data into a sealed
+/// `SecretData`. Secrets of parked profiles are named `profiles/{id}/{key}`.
+pub fn seal_store_data(dir: &Path, value: &mut Value) -> Result<SecretData, String> {
+    let mut plain = HashMap::new();
+    if let Some(data) = value.get_mut("data").and_then(|x| x.as_object_mut()) {
+        for key in SECRET_KEYS {
+            if let Some(Value::String(v)) = data.remove(*key) {
+                plain.insert(key.to_string(), v);
+            }
+        }
+    }
+    if let Some(profiles) = value.get_mut("profiles").and_then(|x| x.as_array_mut()) {
+        for profile in profiles {
+            let id = profile["id"].as_str().unwrap_or_default().to_string();
+            if let Some(data) = profile.get_mut("data").and_then(|x| x.as_object_mut()) {
+                for key in SECRET_KEYS {
+                    if let Some(Value::String(v)) = data.remove(*key) {
+                        plain.insert(format!("profiles/{}/{}", id, key), v);
+                    }
+                }
+            }
+        }
+    }
+
+    let secret = SecretBox::load_or_create(dir)?;
+    let mut secrets = SecretData {
+        version: SECRET_DATA_VERSION,
+        entries: HashMap::with_capacity(plain.len()),
+    };
+    for (name, v) in plain {
+        let sealed = secret.seal(&name, &v)?;
+        secrets.entries.insert(name, sealed);
+    }
+    Ok(secrets)
+}
+
+/// Puts the secrets sealed by `seal_store_data` back into the store. Values
+/// already in the store win, entries that fail to open are skipped.
+pub fn open_store_data(
+    dir: &Path,
+    secrets: SecretData,
+    store_data: &StoreData,
+) -> Result<(), String> {
+    let secret = SecretBox::load_or_create(dir)?;
+    for (name, sealed) in secrets.entries {
+        let v = match secret.open(&name, &sealed) {
+            Ok(v) => v,
+            Err(e) => {
+                log::warn!("Failed to open secret {}: {}", name, e);
+                continue;
+            }
+        };
+
+        match name
+            .strip_prefix("profiles/")
+            .and_then(|x| x.rsplit_once('/'))
+        {
+            Some((id, key)) => {
+                if let Some(profile) = store_data.profiles.write().iter_mut().find(|x| x.id == id) {
+                    profile.data.entry(key.to_string()).or_insert(v);
+                }
+            }
+            None => {
+                store_data.data.entry(name).or_insert(v);
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Writes the key to a temp file readable by the current user only and
+/// renames it into place, so a crash never leaves a short key. The app data
+/// dir is already private to the user on other platforms.
+fn write_key_file(path: &Path, key: &Key) -> Result<(), String> {
+    use std::io::Write;
+
+    let tmp = path.with_extension("key.tmp");
+    let _ = std::fs::remove_file(&tmp);
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+
+    let mut file = options
+        .open(&tmp)
+        .map_err(|e| format!("Failed to create secret key: {}", e))?;
+    file.write_all(key)
+        .and_then(|_| file.sync_all())
+        .map_err(|e| format!("Failed to write secret key: {}", e))?;
+    std::fs::rename(&tmp, path).map_err(|e| format!("Failed to write secret key: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_seal_open() {
+        let dir = tempfile::tempdir().unwrap();
+
+        let secret = SecretBox::load_or_create(dir.path()).unwrap();
+        let sealed = secret.seal("accessToken", "token").unwrap();
+        assert_ne!(sealed, "token");
+
+        let secret = SecretBox::load_or_create(dir.path()).unwrap();
+        assert_eq!(secret.open("accessToken", &sealed).unwrap(), "token");
+        assert!(secret.open("user", &sealed).is_err());
+    }
+
+    #[test]
+    fn test_replace_invalid_key() {
+        let dir = tempfile::tempdir().unwrap();
+        let sealed = SecretBox::load_or_create(dir.path())
+            .unwrap()
+            .seal("accessToken", "token")
+            .unwrap();
+
+        // Torn write of an older version.
+        std::fs::write(dir.path().join(KEY_FILE), [0; 7]).unwrap();
+        let secret = SecretBox::load_or_create(dir.path()).unwrap();
+        assert!(secret.open("accessToken", &sealed).is_err());
+        assert_eq!(std::fs::read(dir.path().join(KEY_FILE)).unwrap().len(), 32);
+        assert!(SecretBox::load_or_create(dir.path()).is_ok());
+    }
+}
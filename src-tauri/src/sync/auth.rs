@@ -0,0 +1,153 @@
+use std::{
+    sync::atomic::{AtomicBool, Ordering},
+    time::Duration,
+};
+
+use chrono::{DateTime, Utc};
+use parking_lot::Mutex;
+use serde::Serialize;
+use tauri::{AppHandle, Emitter, Manager, Runtime, State};
+
+use crate::{
+    client::{
+        ApiError,
+        auth::{AuthFailure, token_expiry},
+    },
+    store::model::AppState,
+};
+
+pub const AUTH_EXPIRED_EVENT: &str = "auth-expired";
+
+/// How long before the token runs out the frontend is asked to log in again.
+const EXPIRY_WARNING: chrono::Duration = chrono::Duration::minutes(10);
+const CHECK_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Payload of `AUTH_EXPIRED_EVENT`, `expired` is false for the advance warning.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AuthStatus {
+    pub expired: bool,
+    pub expires_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Default)]
+pub struct AuthState {
+    /// Set on a 401 or once the token is past its expiry. Sync and outbox
+    /// replay stay paused until a new token is stored.
+    expired: AtomicBool,
+    /// The token the advance warning was sent for, it is sent once per token.
+    warned: Mutex<String>,
+}
+
+impl AuthState {
+    pub fn is_expired(&self) -> bool {
+        self.expired.load(Ordering::Relaxed)
+    }
+
+    pub fn reset(&self) {
+        self.expired.store(false, Ordering::Relaxed);
+        self.warned.lock().clear();
+    }
+}
+
+#[tauri::command]
+pub async fn get_auth_status(
+    state: State<'_, AppState>,
+    auth_state: State<'_, AuthState>,
+) -> Result<AuthStatus, String> {
+    log::trace!("[get_auth_status]");
+
+    let expires_at = state
+        .store
+        .data
+        .get("accessToken")
+        .and_then(|v| token_expiry(&v));
+    Ok(AuthStatus {
+        expired: auth_state.is_expired() || expires_at.is_some_and(|t| t <= Utc::now()),
+        expires_at,
+    })
+}
+
+/// Checks the token expiry every minute and warns the frontend ahead of time.
+pub fn start_auth_watch(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        loop {
+            check_token_expiry(&app);
+            tokio::time::sleep(CHECK_INTERVAL).await;
+        }
+    });
+}
+
+fn check_token_expiry(app: &AppHandle) {
+    let state = app.state::<AppState>();
+    let auth_state = app.state::<AuthState>();
+    if auth_state.is_expired() {
+        return;
+    }
+
+    let Some(token) = state.store.data.get("accessToken").map(|v| v.clone()) else {
+        return;
+    };
+    let Some(expires_at) = token_expiry(&token) else {
+        return;
+    };
+
+    let now = Utc::now();
+    if expires_at <= now {
+        expire(app, Some(expires_at));
+    } else if expires_at - now <= EXPIRY_WARNING {
+        let mut warned = auth_state.warned.lock();
+        if *warned != token {
+            *warned = token;
+            log::info!("Access token expires at {}", expires_at);
+            emit_status(
+                app,
+                AuthStatus {
+                    expired: false,
+                    expires_at: Some(expires_at),
+                },
+            );
+        }
+    }
+}
+
+/// Marks the login expired after a 401. Returns whether `e` was one.
+pub fn check_auth_error<R: Runtime>(app: &AppHandle<R>, e: &ApiError) -> bool {
+    if e.auth_failure() != Some(AuthFailure::Unauthenticated) {
+        return false;
+    }
+
+    let expires_at = app
+        .state::<AppState>()
+        .store
+        .data
+        .get("accessToken")
+        .and_then(|v| token_expiry(&v));
+    expire(app, expires_at);
+    true
+}
+
+fn expire<R: Runtime>(app: &AppHandle<R>, expires_at: Option<DateTime<Utc>>) {
+    if app
+        .state::<AuthState>()
+        .expired
+        .swap(true, Ordering::Relaxed)
+    {
+        return;
+    }
+
+    log::warn!("Access token expired, pausing sync until next login");
+    emit_status(
+        app,
+        AuthStatus {
+            expired: true,
+            expires_at,
+        },
+    );
+}
+
+fn emit_status<R: Runtime>(app: &AppHandle<R>, status: AuthStatus) {
+    if let Err(e) = app.emit(AUTH_EXPIRED_EVENT, status) {
+        log::error!("Failed to emit auth expired: {}", e);
+    }
+}
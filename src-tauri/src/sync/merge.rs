@@ -0,0 +1,167 @@
+use crate::store::model::{Memo, MemoBase};
+
+/// Result of merging a memo edited both locally and on the server.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MergedMemo {
+    pub content: String,
+    pub pinned: bool,
+}
+
+/// Three-way merges `content` and `pinned` of the local and remote versions
+/// against the version the local edit started from, the server derives the
+/// tags from the merged content. Returns `None` when both sides changed the
+/// same lines differently.
+pub fn merge_memo(base: &MemoBase, local: &Memo, remote: &Memo) -> Option<MergedMemo> {
+    let content = merge_lines(&base.content, &local.content, &remote.content)?;
+
+    let pinned = if local.pinned != base.pinned {
+        local.pinned
+    } else {
+        remote.pinned
+    };
+
+    Some(MergedMemo { content, pinned })
+}
+
+/// Line based three-way merge (diff3). Lines keep their line endings so a
+/// clean merge reproduces the input byte for byte.
+pub fn merge_lines(base: &str, local: &str, remote: &str) -> Option<String> {
+    if local == remote || remote == base {
+        return Some(local.to_string());
+    }
+    if local == base {
+        return Some(remote.to_string());
+    }
+
+    let base = base.split_inclusive('\n').collect::<Vec<_>>();
+    let local = local.split_inclusive('\n').collect::<Vec<_>>();
+    let remote = remote.split_inclusive('\n').collect::<Vec<_>>();
+
+    let local_match = match_lines(&base, &local);
+    let remote_match = match_lines(&base, &remote);
+
+    let mut result = Vec::new();
+    let (mut b, mut l, mut r) = (0, 0, 0);
+    loop {
+        // Next base line kept unchanged by both sides.
+        let stable = (b..base.len()).find_map(|k| match (local_match[k], remote_match[k]) {
+            (Some(lk), Some(rk)) if lk >= l && rk >= r => Some((k, lk, rk)),
+            _ => None,
+        });
+
+        let (k, lk, rk) = stable.unwrap_or((base.len(), local.len(), remote.len()));
+        result.extend(merge_chunk(&base[b..k], &local[l..lk], &remote[r..rk])?);
+
+        if k == base.len() {
+            break;
+        }
+        result.push(base[k]);
+        (b, l, r) = (k + 1, lk + 1, rk + 1);
+    }
+
+    Some(result.concat())
+}
+
+fn merge_chunk<'a>(
+    base: &[&'a str],
+    local: &[&'a str],
+    remote: &[&'a str],
+) -> Option<Vec<&'a str>> {
+    if local == remote || remote == base {
+        Some(local.to_vec())
+    } else if local == base {
+        Some(remote.to_vec())
+    } else {
+        None
+    }
+}
+
+/// Longest common subsequence of lines, as a map from each line of `base` to
+/// its matching line in `other`.
+fn match_lines(base: &[&str], other: &[&str]) -> Vec<Option<usize>> {
+    let (n, m) = (base.len(), other.len());
+    let mut lcs = vec![vec![0u32; m + 1]; n + 1];
+    for i in (0..n).rev() {
+        for j in (0..m).rev() {
+            lcs[i][j] = if base[i] == other[j] {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                lcs[i + 1][j].max(lcs[i][j + 1])
+            };
+        }
+    }
+
+    let mut matches = vec![None; n];
+    let (mut i, mut j) = (0, 0);
+    while i < n && j < m {
+        if base[i] == other[j] {
+            matches[i] = Some(j);
+            i += 1;
+            j += 1;
+        } else if lcs[i + 1][j] >= lcs[i][j + 1] {
+            i += 1;
+        } else {
+            j += 1;
+        }
+    }
+
+    matches
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_merge_lines() {
+        let base = "a\nb\nc\nd\n";
+
+        assert_eq!(
+            merge_lines(base, "a\nB\nc\nd\n", "a\nb\nc\nD\n").as_deref(),
+            Some("a\nB\nc\nD\n")
+        );
+        assert_eq!(
+            merge_lines(base, "x\na\nb\nc\nd\n", "a\nb\nc\nd\ny").as_deref(),
+            Some("x\na\nb\nc\nd\ny")
+        );
+        assert_eq!(
+            merge_lines(base, "a\nc\nd\n", "a\nb\nc\nd\ne\n").as_deref(),
+            Some("a\nc\nd\ne\n")
+        );
+        assert_eq!(merge_lines(base, "a\nB\nc\nd\n", "a\nX\nc\nd\n"), None);
+        assert_eq!(
+            merge_lines(base, "a\nX\nc\nd\n", "a\nX\nc\nd\n").as_deref(),
+            Some("a\nX\nc\nd\n")
+        );
+    }
+
+    #[test]
+    fn test_merge_memo() {
+        let base = MemoBase {
+            content: "#a\nhello\n".to_string(),
+            tags: vec!["a".to_string()],
+            pinned: false,
+            ..Default::default()
+        };
+        let local = Memo {
+            content: "#a #b\nhello\n".to_string(),
+            tags: vec!["a".to_string(), "b".to_string()],
+            pinned: true,
+            ..Default::default()
+        };
+        let remote = Memo {
+            content: "#a\nhello\nworld\n".to_string(),
+            tags: vec!["a".to_string()],
+            pinned: false,
+            ..Default::default()
+        };
+
+        assert_eq!(
+            merge_memo(&base, &local, &remote),
+            Some(MergedMemo {
+                content: "#a #b\nhello\nworld\n".to_string(),
+                pinned: true,
+            })
+        );
+    }
+}
@@ -0,0 +1,387 @@
+use std::{
+    collections::HashSet,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use chrono::{DateTime, Utc};
+use reqwest::StatusCode;
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Emitter, Manager, Runtime, State};
+
+use crate::{
+    api::models::{V1Direction, V1State},
+    client::{
+        ApiError, MemosClient,
+        compat::{ServerCompat, ServerVersion},
+        memo::{DEFAULT_PAGE_SIZE, ListMemosRequest},
+    },
+    store::{
+        memo::{cache_memo, get_memo_cache_path, uncache_memo},
+        model::{AppState, Memo, StoreData},
+        outbox::is_local_memo,
+        save_store_data, schema,
+    },
+    utils::time::is_later,
+};
+
+pub mod auth;
+pub mod merge;
+pub mod outbox;
+pub mod shortcut;
+
+pub const SYNC_PROGRESS_EVENT: &str = "memo-sync-progress";
+/// Store key of the version last reported by the server.
+pub const SERVER_VERSION_KEY: &str = "serverVersion";
+
+const SYNC_INTERVAL: Duration = Duration::from_secs(5 * 60);
+const FULL_SYNC_INTERVAL: chrono::Duration = chrono::Duration::hours(24);
+
+/// Serializes sync passes, a pass started while another one runs waits for it.
+#[derive(Debug, Default)]
+pub struct SyncState {
+    pub lock: tokio::sync::Mutex<()>,
+}
+
+/// Per-account sync bookkeeping, stored in the account's memo cache dir.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SyncMeta {
+    /// Greatest `update_time` of all memos pulled so far.
+    pub update_time: String,
+    pub last_full_sync: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum SyncStage {
+    Started,
+    Progress,
+    Finished,
+    Failed,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SyncProgress {
+    pub stage: SyncStage,
+    pub full: bool,
+    /// Memos fetched from the server in this pass.
+    pub fetched: usize,
+    /// Memos whose cached copy changed in this pass.
+    pub updated: usize,
+    pub removed: usize,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ServerInfo {
+    pub version: String,
+    pub compat: ServerCompat,
+}
+
+/// Probes the server version, meant to be called right after login. The
+/// version is persisted so every later client speaks the server's dialect.
+#[tauri::command]
+pub async fn detect_server_version(state: State<'_, AppState>) -> Result<ServerInfo, String> {
+    log::info!("[detect_server_version]");
+
+    let version = probe_server_version(&state).await?;
+    Ok(ServerInfo {
+        version: version.to_string(),
+        compat: ServerCompat::from_version(version),
+    })
+}
+
+#[tauri::command]
+pub async fn sync_memos(
+    app: AppHandle,
+    sync_state: State<'_, SyncState>,
+    full: bool,
+) -> Result<SyncProgress, String> {
+    log::info!("[sync_memos] full: {}", full);
+
+    let _guard = sync_state.lock.lock().await;
+    run_sync(&app, full).await
+}
+
+/// Runs one incremental pass in the background, e.g. after switching profiles.
+pub fn spawn_sync(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        let sync_state = app.state::<SyncState>();
+        let _guard = sync_state.lock.lock().await;
+        if let Err(e) = run_sync(&app, false).await {
+            log::warn!("Memo sync failed: {}", e);
+        }
+    });
+}
+
+pub fn start_memo_sync(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        loop {
+            {
+                let sync_state = app.state::<SyncState>();
+                let _guard = sync_state.lock.lock().await;
+                if let Err(e) = run_sync(&app, false).await {
+                    log::warn!("Background memo sync failed: {}", e);
+                }
+            }
+
+            tokio::time::sleep(SYNC_INTERVAL).await;
+        }
+    });
+}
+
+pub fn build_client(store: &StoreData) -> Result<MemosClient, String> {
+    let server_url = store.server_url.read().clone();
+    let access_token = store
+        .data
+        .get("accessToken")
+        .map(|v| v.clone())
+        .unwrap_or_default();
+    if server_url.is_empty() || access_token.is_empty() {
+        return Err("Host info not found, maybe not login".to_string());
+    }
+
+    let client = MemosClient::new(&server_url, &access_token).map_err(|e| e.to_string())?;
+    let version = store
+        .data
+        .get(SERVER_VERSION_KEY)
+        .and_then(|v| ServerVersion::parse(&v));
+
+    Ok(match version {
+        Some(version) => client.with_server_version(version),
+        None => client,
+    })
+}
+
+async fn probe_server_version(state: &AppState) -> Result<ServerVersion, String> {
+    let client = build_client(&state.store)?;
+    let version = client
+        .probe_server_version()
+        .await
+        .map_err(|e| format!("Failed to detect server version: {}", e))?;
+    log::info!("Server version: {}", version);
+
+    state
+        .store
+        .data
+        .insert(SERVER_VERSION_KEY.to_string(), version.to_string());
+    save_store_data(state).await?;
+
+    Ok(version)
+}
+
+/// Runs one sync pass. An incremental pass pulls memos changed since the
+/// stored watermark; a full pass pulls everything and drops cached memos the
+/// server no longer has. A full pass is forced when there is no watermark yet
+/// or the last one is older than a day. Queued offline edits are replayed
+/// first and memos that still have some are left alone. The server version is
+/// probed again on full passes and when listing memos answers 404, servers
+/// can be upgraded under a running client.
+pub async fn run_sync<R: Runtime>(app: &AppHandle<R>, full: bool) -> Result<SyncProgress, String> {
+    let state = app.state::<AppState>();
+    if app.state::<auth::AuthState>().is_expired() {
+        return Err("Login expired, please login again".to_string());
+    }
+
+    let memo_cache_dir = get_memo_cache_path(
+        &app.path()
+            .app_cache_dir()
+            .map_err(|e| format!("Failed to get app cache dir: {}", e))?,
+        &state.store.server_url.read(),
+        &state.store.user_name.read(),
+    )?;
+
+    let mut meta = load_sync_meta(&memo_cache_dir).await;
+    let full = full
+        || meta.update_time.is_empty()
+        || meta
+            .last_full_sync
+            .is_none_or(|t| Utc::now() - t > FULL_SYNC_INTERVAL);
+
+    // Logins from before version detection existed have no stored version.
+    if (full || !state.store.data.contains_key(SERVER_VERSION_KEY))
+        && let Err(e) = probe_server_version(&state).await
+    {
+        log::warn!("{}, keeping the stored version", e);
+    }
+
+    outbox::replay_outbox(app).await?;
+
+    let mut client = build_client(&state.store)?;
+
+    let mut progress = SyncProgress {
+        stage: SyncStage::Started,
+        full,
+        fetched: 0,
+        updated: 0,
+        removed: 0,
+        error: None,
+    };
+    emit_progress(app, &progress);
+
+    let mut result = pull_memos(app, &state, &client, &mut meta, &mut progress).await;
+    if let Err(e) = &result
+        && e.status() == Some(StatusCode::NOT_FOUND)
+    {
+        log::info!("Memos not found, detecting server version again");
+        match probe_server_version(&state).await {
+            Ok(_) => {
+                client = build_client(&state.store)?;
+                result = pull_memos(app, &state, &client, &mut meta, &mut progress).await;
+            }
+            Err(e) => log::warn!("{}", e),
+        }
+    }
+    let result = result.map_err(|e| format!("Failed to list memos: {}", e));
+    if result.is_ok()
+        && let Err(e) = shortcut::pull_shortcuts(app, &state).await
+    {
+        log::warn!("{}", e);
+    }
+
+    match result {
+        Ok(()) => {
+            if full {
+                meta.last_full_sync = Some(Utc::now());
+            }
+            save_sync_meta(&memo_cache_dir, &meta).await?;
+            progress.stage = SyncStage::Finished;
+            emit_progress(app, &progress);
+            log::info!(
+                "Memo sync finished, full: {}, fetched: {}, updated: {}, removed: {}",
+                progress.full,
+                progress.fetched,
+                progress.updated,
+                progress.removed
+            );
+            Ok(progress)
+        }
+        Err(e) => {
+            progress.stage = SyncStage::Failed;
+            progress.error = Some(e.clone());
+            emit_progress(app, &progress);
+            Err(e)
+        }
+    }
+}
+
+async fn pull_memos<R: Runtime>(
+    app: &AppHandle<R>,
+    state: &AppState,
+    client: &MemosClient,
+    meta: &mut SyncMeta,
+    progress: &mut SyncProgress,
+) -> Result<(), ApiError> {
+    let watermark = if progress.full {
+        String::new()
+    } else {
+        meta.update_time.clone()
+    };
+    let mut seen = HashSet::new();
+
+    for memo_state in [V1State::Normal, V1State::Archived] {
+        let mut request = ListMemosRequest {
+            page_size: Some(DEFAULT_PAGE_SIZE),
+            state: Some(memo_state),
+            sort: Some("update_time".to_string()),
+            direction: Some(V1Direction::Desc),
+            ..Default::default()
+        };
+
+        loop {
+            let response = client.list_memos(&request).await.inspect_err(|e| {
+                auth::check_auth_error(app, e);
+            })?;
+            let memos = response.memos.unwrap_or_default();
+
+            // Pages are ordered by update time, once a whole page is older
+            // than the watermark the rest has been pulled before.
+            let mut has_newer = false;
+            for memo in memos {
+                let memo = Memo::from(memo);
+                progress.fetched += 1;
+
+                if watermark.is_empty() || is_later(&memo.update_time, &watermark) {
+                    has_newer = true;
+                }
+                if is_later(&memo.update_time, &meta.update_time) {
+                    meta.update_time = memo.update_time.clone();
+                }
+
+                seen.insert(memo.name.clone());
+                if state.outbox().has_pending(&memo.name) {
+                    continue;
+                }
+                if cache_memo(state, memo.name.clone(), memo) {
+                    progress.updated += 1;
+                }
+            }
+
+            progress.stage = SyncStage::Progress;
+            emit_progress(app, progress);
+
+            match response.next_page_token {
+                Some(token) if !token.is_empty() && has_newer => request.page_token = Some(token),
+                _ => break,
+            }
+        }
+    }
+
+    if progress.full {
+        let removed = state
+            .cache()
+            .all_memo_meta
+            .iter()
+            .filter(|x| !seen.contains(x.key()))
+            .filter(|x| !is_local_memo(x.key()) && !state.outbox().has_pending(x.key()))
+            .map(|x| x.key().clone())
+            .collect::<Vec<_>>();
+        for memo_name in removed {
+            uncache_memo(state, &memo_name);
+            progress.removed += 1;
+        }
+    }
+
+    Ok(())
+}
+
+fn emit_progress<R: Runtime>(app: &AppHandle<R>, progress: &SyncProgress) {
+    if let Err(e) = app.emit(SYNC_PROGRESS_EVENT, progress.clone()) {
+        log::error!("Failed to emit sync progress: {}", e);
+    }
+}
+
+fn get_sync_meta_path(memo_cache_dir: &Path) -> PathBuf {
+    memo_cache_dir.join("memos").join("sync_meta.json")
+}
+
+async fn load_sync_meta(memo_cache_dir: &Path) -> SyncMeta {
+    let path = get_sync_meta_path(memo_cache_dir);
+    schema::SYNC_META
+        .read_async(path)
+        .await
+        .unwrap_or_else(|e| {
+            log::error!("Failed to read sync meta, doing a full sync: {}", e);
+            None
+        })
+        .unwrap_or_default()
+}
+
+async fn save_sync_meta(memo_cache_dir: &Path, meta: &SyncMeta) -> Result<(), String> {
+    let path = get_sync_meta_path(memo_cache_dir);
+    if let Some(parent) = path.parent() {
+        tokio::fs::create_dir_all(parent)
+            .await
+            .map_err(|e| format!("Failed to create memo cache dir: {}", e))?;
+    }
+
+    let content =
+        serde_json::to_string(meta).map_err(|e| format!("Failed to serialize sync meta: {}", e))?;
+
+    schema::SYNC_META
+        .write_str_async(path, content)
+        .await
+        .map_err(|e| format!("Failed to write sync meta: {}", e))
+}
@@ -0,0 +1,325 @@
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use reqwest::StatusCode;
+use serde::Serialize;
+use tauri::{AppHandle, Emitter, Manager, Runtime, State};
+
+use crate::{
+    api::models::{Apiv1Memo, TheMemoToUpdateTheNameFieldIsRequired, V1Visibility},
+    client::{ApiError, MemosClient},
+    store::{
+        memo::{cache_memo, load_memo, uncache_memo},
+        model::{AppState, Memo, MemoBase},
+        outbox::{MemoConflict, OutboxEntry, OutboxOp, save_outbox},
+    },
+    sync::{
+        SyncState,
+        auth::{AuthState, check_auth_error},
+        build_client,
+        merge::merge_memo,
+    },
+    utils::time::is_later,
+};
+
+pub const MEMO_RENAMED_EVENT: &str = "memo-renamed";
+pub const MEMO_CONFLICT_EVENT: &str = "memo-conflict";
+
+const IDLE_INTERVAL: Duration = Duration::from_secs(60);
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MemoRenamed {
+    pub from: String,
+    pub to: String,
+}
+
+#[tauri::command]
+pub async fn flush_outbox(
+    app: AppHandle,
+    sync_state: State<'_, SyncState>,
+) -> Result<usize, String> {
+    log::info!("[flush_outbox]");
+
+    let _guard = sync_state.lock.lock().await;
+    let state = app.state::<AppState>();
+
+    // A manual flush means the user believes the network is back.
+    for entry in state.outbox().data.write().entries.iter_mut() {
+        entry.next_retry_at = None;
+    }
+
+    replay_outbox(&app).await.map(|(replayed, _)| replayed)
+}
+
+/// Replays queued operations whenever one is queued or a retry is due.
+pub fn start_outbox_replay(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        loop {
+            let next_retry_at = {
+                let sync_state = app.state::<SyncState>();
+                let _guard = sync_state.lock.lock().await;
+                match replay_outbox(&app).await {
+                    Ok((_, next_retry_at)) => next_retry_at,
+                    Err(e) => {
+                        log::warn!("Failed to replay outbox: {}", e);
+                        None
+                    }
+                }
+            };
+
+            let wait = next_retry_at
+                .and_then(|t| (t - Utc::now()).to_std().ok())
+                .unwrap_or(IDLE_INTERVAL)
+                .min(IDLE_INTERVAL);
+
+            let outbox = app.state::<AppState>().outbox();
+            tokio::select! {
+                _ = outbox.notify.notified() => {}
+                _ = tokio::time::sleep(wait) => {}
+            }
+        }
+    });
+}
+
+/// Replays the outbox in order until it is empty or an operation has to wait
+/// for a retry. Returns the number of replayed operations and when the next
+/// retry is due. Callers must hold the `SyncState` lock.
+pub async fn replay_outbox<R: Runtime>(
+    app: &AppHandle<R>,
+) -> Result<(usize, Option<DateTime<Utc>>), String> {
+    let state = app.state::<AppState>();
+    // Paused until the next login, nothing is lost by waiting.
+    if state.outbox().front().is_none() || app.state::<AuthState>().is_expired() {
+        return Ok((0, None));
+    }
+
+    let client = build_client(&state.store)?;
+    let mut replayed = 0;
+
+    while let Some(entry) = state.outbox().begin() {
+        if !entry.in_flight
+            && let Some(next_retry_at) = entry.next_retry_at
+        {
+            return Ok((replayed, Some(next_retry_at)));
+        }
+
+        match apply(app, &state, &client, &entry).await {
+            Ok(()) => replayed += 1,
+            Err(e) if check_auth_error(app, &e) => {
+                log::warn!("Outbox paused, login expired, op: {:?}", entry.op);
+                return Ok((replayed, None));
+            }
+            Err(e) if is_permanent(&e) => {
+                log::error!(
+                    "Outbox operation rejected, op: {:?}, error: {}",
+                    entry.op,
+                    e
+                );
+                state.outbox().fail(entry.id, e.to_string());
+            }
+            Err(e) => {
+                log::warn!("Outbox operation failed, op: {:?}, error: {}", entry.op, e);
+                state.outbox().retry_later(entry.id, e.to_string());
+                save_outbox(app, &state).await?;
+                return Ok((
+                    replayed,
+                    state.outbox().front().and_then(|x| x.next_retry_at),
+                ));
+            }
+        }
+
+        save_outbox(app, &state).await?;
+    }
+
+    log::info!("Outbox replayed, operations: {}", replayed);
+    Ok((replayed, None))
+}
+
+async fn apply<R: Runtime>(
+    app: &AppHandle<R>,
+    state: &AppState,
+    client: &MemosClient,
+    entry: &OutboxEntry,
+) -> Result<(), ApiError> {
+    let memo_name = entry.op.memo_name().to_string();
+
+    let mut update = match &entry.op {
+        OutboxOp::Create {
+            content,
+            visibility,
+            resources,
+            ..
+        } => {
+            let memo = Apiv1Memo {
+                content: Some(content.clone()),
+                visibility: Some(parse_visibility(visibility)),
+                resources: Some(resources.clone()),
+                ..Default::default()
+            };
+            let memo = Memo::from(client.create_memo(&memo).await?);
+
+            state
+                .outbox()
+                .complete(entry.id, Some((&memo_name, &memo.name)));
+            rename_cached_memo(app, state, &memo_name, memo);
+            return Ok(());
+        }
+        OutboxOp::Update {
+            content,
+            visibility,
+            ..
+        } => TheMemoToUpdateTheNameFieldIsRequired {
+            content: content.clone(),
+            visibility: visibility.as_deref().map(parse_visibility),
+            ..Default::default()
+        },
+        OutboxOp::SetState {
+            state: memo_state, ..
+        } => TheMemoToUpdateTheNameFieldIsRequired {
+            state: Some(*memo_state),
+            ..Default::default()
+        },
+        OutboxOp::Pin { pinned, .. } => TheMemoToUpdateTheNameFieldIsRequired {
+            pinned: Some(*pinned),
+            ..Default::default()
+        },
+        OutboxOp::Delete { .. } => {
+            match client.delete_memo(&memo_name).await {
+                Err(e) if e.status() == Some(StatusCode::NOT_FOUND) => {}
+                r => r?,
+            }
+            state.outbox().complete(entry.id, None);
+            return Ok(());
+        }
+    };
+
+    if !reconcile(app, state, client, entry, &mut update).await? {
+        return Ok(());
+    }
+    let memo = Memo::from(client.update_memo(&memo_name, &update).await?);
+
+    state.outbox().complete(entry.id, None);
+    if !state.outbox().has_pending(&memo_name) {
+        cache_memo(state, memo_name, memo);
+    } else if let Ok(Some(mut local)) = load_memo(state, &memo_name) {
+        // Later local edits of the memo are still queued, keep showing them
+        // but merge them against what the server has now, not our own push.
+        local.base = Some(MemoBase::from(&memo));
+        cache_memo(state, memo_name, local);
+    }
+
+    Ok(())
+}
+
+/// Checks whether the memo changed on the server since the local edit started
+/// and merges both sides into `update`. Returns `false` when the edit could not
+/// be merged and was parked as a conflict instead.
+async fn reconcile<R: Runtime>(
+    app: &AppHandle<R>,
+    state: &AppState,
+    client: &MemosClient,
+    entry: &OutboxEntry,
+    update: &mut TheMemoToUpdateTheNameFieldIsRequired,
+) -> Result<bool, ApiError> {
+    let memo_name = entry.op.memo_name();
+    let local = match load_memo(state, memo_name) {
+        Ok(Some(local)) => local,
+        Ok(None) => return Ok(true),
+        Err(e) => {
+            log::warn!("Failed to load local memo {}: {}", memo_name, e);
+            return Ok(true);
+        }
+    };
+    let Some(base) = &local.base else {
+        return Ok(true);
+    };
+
+    let remote = Memo::from(client.get_memo(memo_name).await?);
+    if !is_later(&remote.update_time, &base.update_time) {
+        return Ok(true);
+    }
+
+    match merge_memo(base, &local, &remote) {
+        Some(merged) => {
+            log::info!("[outbox] memo changed on server, merged: {}", memo_name);
+            // Other operations only carry the merged pin, the content goes
+            // with the update that changed it.
+            if update.content.is_some() {
+                update.content = Some(merged.content);
+            }
+            if update.pinned.is_none() && merged.pinned != remote.pinned {
+                update.pinned = Some(merged.pinned);
+            }
+            Ok(true)
+        }
+        None => {
+            log::warn!("[outbox] memo changed on server, conflict: {}", memo_name);
+            let conflict = MemoConflict {
+                memo_name: memo_name.to_string(),
+                base: base.clone(),
+                local: local.clone(),
+                remote: remote.clone(),
+                detected_at: Utc::now(),
+                pending: Vec::new(),
+            };
+            state.outbox().add_conflict(conflict.clone());
+            cache_memo(state, memo_name.to_string(), remote);
+
+            if let Err(e) = app.emit(MEMO_CONFLICT_EVENT, conflict) {
+                log::error!("Failed to emit memo conflict: {}", e);
+            }
+            Ok(false)
+        }
+    }
+}
+
+/// Moves a memo created offline to the name the server assigned. Edits
+/// queued while it was being created stay in the cache, a memo deleted
+/// meanwhile stays gone.
+fn rename_cached_memo<R: Runtime>(app: &AppHandle<R>, state: &AppState, from: &str, memo: Memo) {
+    let to = memo.name.clone();
+    log::info!("[outbox] memo created, local: {}, server: {}", from, to);
+
+    let local = load_memo(state, from).ok().flatten();
+    uncache_memo(state, from);
+    if !state.outbox().is_deleted(&to) {
+        let memo = match local {
+            Some(local) if state.outbox().has_pending(&to) => Memo {
+                name: memo.name,
+                create_time: memo.create_time,
+                ..local
+            },
+            _ => memo,
+        };
+        cache_memo(state, to.clone(), memo);
+    }
+
+    if let Err(e) = app.emit(
+        MEMO_RENAMED_EVENT,
+        MemoRenamed {
+            from: from.to_string(),
+            to,
+        },
+    ) {
+        log::error!("Failed to emit memo renamed: {}", e);
+    }
+}
+
+fn parse_visibility(visibility: &str) -> V1Visibility {
+    serde_json::from_value(serde_json::Value::String(visibility.to_string()))
+        .unwrap_or(V1Visibility::Private)
+}
+
+/// Client errors other than timeout and rate limiting will fail again on every
+/// retry. A 401 never gets here, it pauses the outbox instead.
+fn is_permanent(e: &ApiError) -> bool {
+    match e.status() {
+        Some(code) => {
+            code.is_client_error()
+                && code != StatusCode::REQUEST_TIMEOUT
+                && code != StatusCode::TOO_MANY_REQUESTS
+        }
+        None => matches!(e, ApiError::Decode(_) | ApiError::InvalidConfig(_)),
+    }
+}
@@ -0,0 +1,184 @@
+use tauri::{AppHandle, Manager, Runtime, State};
+
+use crate::{
+    api::models::{Apiv1Shortcut, ShortcutServiceUpdateShortcutRequest},
+    client::MemosClient,
+    store::{
+        cel::FilterExpr,
+        memo::{FilteredMemoPageRequest, MemoPage, filter_memo_page},
+        model::AppState,
+        save_store_data,
+    },
+    sync::{auth::check_auth_error, build_client},
+};
+
+/// Store key of the JSON shortcuts last fetched from the server, one of the
+/// account scoped `PROFILE_KEYS`.
+pub const SHORTCUTS_KEY: &str = "shortcuts";
+
+/// Returns the server's shortcuts, or the cached ones when the server can't
+/// be reached.
+#[tauri::command]
+pub async fn list_shortcuts<R: Runtime>(app: AppHandle<R>) -> Result<Vec<Apiv1Shortcut>, String> {
+    log::trace!("[list_shortcuts]");
+
+    let state = app.state::<AppState>();
+    match pull_shortcuts(&app, &state).await {
+        Ok(shortcuts) => Ok(shortcuts),
+        Err(e) => {
+            log::warn!("{}, using cached shortcuts", e);
+            Ok(cached_shortcuts(&state))
+        }
+    }
+}
+
+#[tauri::command]
+pub async fn create_shortcut<R: Runtime>(
+    app: AppHandle<R>,
+    title: String,
+    filter: String,
+) -> Result<Apiv1Shortcut, String> {
+    log::info!("[create_shortcut] title: {:?}", title);
+
+    let state = app.state::<AppState>();
+    let (client, parent) = shortcut_client(&state)?;
+    let shortcut = Apiv1Shortcut {
+        id: None,
+        title: Some(title),
+        filter: Some(filter),
+    };
+    let created = client
+        .create_shortcut(&parent, &shortcut)
+        .await
+        .map_err(|e| {
+            check_auth_error(&app, &e);
+            format!("Failed to create shortcut: {}", e)
+        })?;
+
+    let mut shortcuts = cached_shortcuts(&state);
+    shortcuts.push(created.clone());
+    save_shortcuts(&state, &shortcuts).await?;
+    Ok(created)
+}
+
+/// `None` fields are left as they are.
+#[tauri::command]
+pub async fn update_shortcut<R: Runtime>(
+    app: AppHandle<R>,
+    id: String,
+    title: Option<String>,
+    filter: Option<String>,
+) -> Result<Apiv1Shortcut, String> {
+    log::info!("[update_shortcut] id: {}", id);
+
+    let state = app.state::<AppState>();
+    let (client, parent) = shortcut_client(&state)?;
+    let updated = client
+        .update_shortcut(
+            &parent,
+            &id,
+            &ShortcutServiceUpdateShortcutRequest { title, filter },
+        )
+        .await
+        .map_err(|e| {
+            check_auth_error(&app, &e);
+            format!("Failed to update shortcut: {}", e)
+        })?;
+
+    let mut shortcuts = cached_shortcuts(&state);
+    match shortcuts.iter_mut().find(|x| x.id == updated.id) {
+        Some(x) => *x = updated.clone(),
+        None => shortcuts.push(updated.clone()),
+    }
+    save_shortcuts(&state, &shortcuts).await?;
+    Ok(updated)
+}
+
+#[tauri::command]
+pub async fn delete_shortcut<R: Runtime>(app: AppHandle<R>, id: String) -> Result<(), String> {
+    log::info!("[delete_shortcut] id: {}", id);
+
+    let state = app.state::<AppState>();
+    let (client, parent) = shortcut_client(&state)?;
+    client.delete_shortcut(&parent, &id).await.map_err(|e| {
+        check_auth_error(&app, &e);
+        format!("Failed to delete shortcut: {}", e)
+    })?;
+
+    let mut shortcuts = cached_shortcuts(&state);
+    shortcuts.retain(|x| x.id.as_deref() != Some(id.as_str()));
+    save_shortcuts(&state, &shortcuts).await
+}
+
+/// Pages through the cached memos matching a cached shortcut, works offline.
+/// The shortcut's filter replaces `request.filter.expression`.
+#[tauri::command]
+pub async fn get_shortcut_memo_page(
+    state: State<'_, AppState>,
+    id: String,
+    mut request: FilteredMemoPageRequest,
+) -> Result<MemoPage, String> {
+    log::trace!(
+        "[get_shortcut_memo_page] id: {}, request: {:?}",
+        id,
+        request
+    );
+
+    let shortcut = cached_shortcuts(&state)
+        .into_iter()
+        .find(|x| x.id.as_deref() == Some(id.as_str()))
+        .ok_or_else(|| format!("Shortcut not found: {}", id))?;
+    let filter = shortcut.filter.unwrap_or_default();
+    request.filter.expression = if filter.trim().is_empty() {
+        None
+    } else {
+        Some(FilterExpr::parse(&filter)?)
+    };
+
+    filter_memo_page(&state, &request)
+}
+
+/// Fetches the shortcuts and replaces the cached ones.
+pub async fn pull_shortcuts<R: Runtime>(
+    app: &AppHandle<R>,
+    state: &AppState,
+) -> Result<Vec<Apiv1Shortcut>, String> {
+    let (client, parent) = shortcut_client(state)?;
+    let shortcuts = client.list_shortcuts(&parent).await.map_err(|e| {
+        check_auth_error(app, &e);
+        format!("Failed to list shortcuts: {}", e)
+    })?;
+
+    save_shortcuts(state, &shortcuts).await?;
+    Ok(shortcuts)
+}
+
+fn shortcut_client(state: &AppState) -> Result<(MemosClient, String), String> {
+    let client = build_client(&state.store)?;
+    Ok((client, state.store.user_name.read().clone()))
+}
+
+fn cached_shortcuts(state: &AppState) -> Vec<Apiv1Shortcut> {
+    state
+        .store
+        .data
+        .get(SHORTCUTS_KEY)
+        .and_then(|x| serde_json::from_str(&x).ok())
+        .unwrap_or_default()
+}
+
+async fn save_shortcuts(state: &AppState, shortcuts: &[Apiv1Shortcut]) -> Result<(), String> {
+    let content = serde_json::to_string(shortcuts)
+        .map_err(|e| format!("Failed to serialize shortcuts: {}", e))?;
+    if state
+        .store
+        .data
+        .get(SHORTCUTS_KEY)
+        .is_some_and(|x| *x == content)
+    {
+        return Ok(());
+    }
+
+    state.store.data.insert(SHORTCUTS_KEY.to_string(), content);
+    save_store_data(state).await
+}
@@ -0,0 +1,239 @@
+use std::{
+    io::Write,
+    path::{Path, PathBuf},
+};
+
+use parking_lot::Mutex;
+
+/// First line of every file, e.g. `#schema:1 fnv1a64:<hex>`.
+const HEADER_PREFIX: &str = "#";
+
+/// Temp file names are fixed per target, writers must take turns.
+static WRITER: Mutex<()> = Mutex::new(());
+
+fn fnv1a64(data: &[u8]) -> u64 {
+    data.iter().fold(0xcbf29ce484222325, |hash, x| {
+        (hash ^ *x as u64).wrapping_mul(0x100000001b3)
+    })
+}
+
+fn with_suffix(path: &Path, suffix: &str) -> PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(suffix);
+    path.with_file_name(name)
+}
+
+fn backup_path(path: &Path) -> PathBuf {
+    with_suffix(path, ".bak")
+}
+
+/// Old copies of `path` that `write_file` and `read_file_with` keep, the
+/// `.bak` and the `.corrupt` one.
+pub fn copies_of(path: &Path) -> [PathBuf; 2] {
+    [backup_path(path), with_suffix(path, ".corrupt")]
+}
+
+/// Splits off and checks the header line, returning the schema version and
+/// the body. Files written before headers were added are version 0, files
+/// with only a checksum are version 0 as well.
+fn verify(content: &str) -> Result<(u32, &str), String> {
+    let Some(rest) = content.strip_prefix(HEADER_PREFIX) else {
+        return Ok((0, content));
+    };
+    let (header, body) = rest
+        .split_once('\n')
+        .ok_or_else(|| "Missing header line".to_string())?;
+
+    let mut version = 0;
+    let mut checksum = None;
+    for field in header.split_whitespace() {
+        match field.split_once(':') {
+            Some(("schema", v)) => {
+                version = v
+                    .parse()
+                    .map_err(|e| format!("Invalid schema version: {}", e))?
+            }
+            Some(("fnv1a64", v)) => {
+                checksum = Some(
+                    u64::from_str_radix(v, 16).map_err(|e| format!("Invalid checksum: {}", e))?,
+                )
+            }
+            _ => {}
+        }
+    }
+
+    let checksum = checksum.ok_or_else(|| "Missing checksum".to_string())?;
+    if fnv1a64(body.as_bytes()) != checksum {
+        return Err("Checksum mismatch".to_string());
+    }
+    Ok((version, body))
+}
+
+fn write_synced(path: &Path, content: &[u8]) -> std::io::Result<()> {
+    let mut file = std::fs::File::create(path)?;
+    file.write_all(content)?;
+    file.sync_all()
+}
+
+/// Writes `content` to a temp file and renames it over `path`, so a crash
+/// leaves either the old or the new file. The previous file is kept as
+/// `.bak` if it was intact.
+pub fn write_file(path: &Path, content: &str, version: u32) -> Result<(), String> {
+    let _writer = WRITER.lock();
+
+    let data = format!(
+        "{}schema:{} fnv1a64:{:016x}\n{}",
+        HEADER_PREFIX,
+        version,
+        fnv1a64(content.as_bytes()),
+        content
+    );
+    let tmp = with_suffix(path, ".tmp");
+    write_synced(&tmp, data.as_bytes())
+        .map_err(|e| format!("Failed to write temp file {}: {}", tmp.to_string_lossy(), e))?;
+
+    if let Ok(old) = std::fs::read_to_string(path)
+        && verify(&old).is_ok()
+    {
+        let bak_tmp = with_suffix(path, ".bak.tmp");
+        if let Err(e) = write_synced(&bak_tmp, old.as_bytes())
+            .and_then(|_| std::fs::rename(&bak_tmp, backup_path(path)))
+        {
+            log::warn!("Failed to back up {}: {}", path.to_string_lossy(), e);
+        }
+    }
+
+    std::fs::rename(&tmp, path)
+        .map_err(|e| format!("Failed to replace {}: {}", path.to_string_lossy(), e))?;
+
+    #[cfg(unix)]
+    if let Some(dir) = path.parent()
+        && let Ok(dir) = std::fs::File::open(dir)
+    {
+        let _ = dir.sync_all();
+    }
+
+    Ok(())
+}
+
+enum ReadError {
+    /// Written by a newer app version, intact but not readable here.
+    Unsupported(String),
+    Corrupt(String),
+}
+
+fn read_file_at<T>(
+    path: &Path,
+    max_version: u32,
+    parse: &impl Fn(u32, &str) -> Result<T, String>,
+) -> Result<T, ReadError> {
+    let content = std::fs::read_to_string(path).map_err(|e| ReadError::Corrupt(e.to_string()))?;
+    let (version, body) = verify(&content).map_err(ReadError::Corrupt)?;
+    if version > max_version {
+        return Err(ReadError::Unsupported(format!(
+            "{} has schema version {}, newer than the supported {}, please update the app",
+            path.to_string_lossy(),
+            version,
+            max_version
+        )));
+    }
+    parse(version, body).map_err(ReadError::Corrupt)
+}
+
+/// Reads a file written by `write_file`, `parse` gets its schema version and
+/// body. A file that fails to verify or parse is moved aside to `.corrupt`
+/// and the `.bak` copy restored. A file newer than `max_version` is an error
+/// and left alone, with its `.bak`, for the app version that wrote it.
+/// `None` if neither exists.
+pub fn read_file_with<T>(
+    path: &Path,
+    max_version: u32,
+    parse: impl Fn(u32, &str) -> Result<T, String>,
+) -> Result<Option<T>, String> {
+    let bak = backup_path(path);
+    if !path.exists() && !bak.exists() {
+        return Ok(None);
+    }
+
+    let error = match read_file_at(path, max_version, &parse) {
+        Ok(data) => return Ok(Some(data)),
+        Err(ReadError::Unsupported(e)) => return Err(e),
+        Err(ReadError::Corrupt(e)) => e,
+    };
+    log::error!("Failed to load {}: {}", path.to_string_lossy(), error);
+
+    if path.exists()
+        && let Err(e) = std::fs::rename(path, with_suffix(path, ".corrupt"))
+    {
+        log::warn!("Failed to move aside {}: {}", path.to_string_lossy(), e);
+    }
+
+    let data = read_file_at(&bak, max_version, &parse).map_err(|e| match e {
+        ReadError::Unsupported(e) => e,
+        ReadError::Corrupt(e) => format!(
+            "Failed to load {} and its backup: {}, {}",
+            path.to_string_lossy(),
+            error,
+            e
+        ),
+    })?;
+    log::warn!("Restoring {} from backup", path.to_string_lossy());
+    if let Err(e) = std::fs::copy(&bak, path) {
+        log::warn!("Failed to restore {}: {}", path.to_string_lossy(), e);
+    }
+
+    Ok(Some(data))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_recover_from_backup() {
+        let read = |path: &Path| {
+            read_file_with(path, 2, |version, body| {
+                Ok((
+                    version,
+                    serde_json::from_str::<Vec<i32>>(body).map_err(|e| e.to_string())?,
+                ))
+            })
+            .unwrap()
+        };
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("store.json");
+
+        write_file(&path, "[1]", 1).unwrap();
+        write_file(&path, "[2]", 2).unwrap();
+        assert_eq!(read(&path), Some((2, vec![2])));
+
+        // Torn write of the newest version.
+        let content = std::fs::read_to_string(&path).unwrap();
+        std::fs::write(&path, &content[..content.len() - 1]).unwrap();
+        assert_eq!(read(&path), Some((1, vec![1])));
+        assert!(with_suffix(&path, ".corrupt").exists());
+        assert_eq!(read(&path), Some((1, vec![1])));
+
+        // Files from before headers.
+        std::fs::write(&path, "[3]").unwrap();
+        assert_eq!(read(&path), Some((0, vec![3])));
+    }
+
+    #[test]
+    fn test_keep_newer_version() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("store.json");
+
+        write_file(&path, "[1]", 1).unwrap();
+        write_file(&path, "[2]", 2).unwrap();
+        let content = std::fs::read_to_string(&path).unwrap();
+        let backup = std::fs::read_to_string(backup_path(&path)).unwrap();
+
+        let e = read_file_with(&path, 1, |_, body| Ok(body.to_string())).unwrap_err();
+        assert!(e.contains("newer than the supported 1"), "{}", e);
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), content);
+        assert_eq!(std::fs::read_to_string(backup_path(&path)).unwrap(), backup);
+        assert!(!with_suffix(&path, ".corrupt").exists());
+    }
+}
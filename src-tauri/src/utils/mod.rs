@@ -1 +1,4 @@
+pub mod file;
 pub mod path;
+pub mod time;
+pub mod zh;
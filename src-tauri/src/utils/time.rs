@@ -0,0 +1,35 @@
+use chrono::{DateTime, Utc};
+
+/// Parses a protobuf JSON timestamp (RFC 3339) as sent by the Memos server.
+pub fn parse_timestamp(time: &str) -> Option<DateTime<Utc>> {
+    DateTime::parse_from_rfc3339(time)
+        .ok()
+        .map(|t| t.with_timezone(&Utc))
+}
+
+/// Returns whether `time` is strictly later than `other`. Unparsable values
+/// are treated as the oldest possible time.
+pub fn is_later(time: &str, other: &str) -> bool {
+    match (parse_timestamp(time), parse_timestamp(other)) {
+        (Some(time), Some(other)) => time > other,
+        (Some(_), None) => true,
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_later() {
+        assert!(is_later("2025-01-02T00:00:00Z", "2025-01-01T23:59:59Z"));
+        assert!(is_later(
+            "2025-01-01T08:00:01+08:00",
+            "2025-01-01T00:00:00Z"
+        ));
+        assert!(!is_later("2025-01-01T00:00:00Z", "2025-01-01T00:00:00Z"));
+        assert!(is_later("2025-01-01T00:00:00Z", ""));
+        assert!(!is_later("", "2025-01-01T00:00:00Z"));
+    }
+}
@@ -0,0 +1,60 @@
+use std::{
+    collections::{HashMap, HashSet},
+    sync::LazyLock,
+};
+
+// Generated by `gen_zh_data.sh`.
+const PINYIN: &str = include_str!("zh/pinyin.txt");
+const T2S: &str = include_str!("zh/t2s.txt");
+
+/// Toneless pinyin of each character, `v` standing for `ü`.
+static SYLLABLES: LazyLock<HashMap<char, &'static str>> = LazyLock::new(|| {
+    PINYIN
+        .lines()
+        .filter_map(|line| line.split_once(' '))
+        .flat_map(|(syllable, chars)| chars.chars().map(move |c| (c, syllable)))
+        .collect()
+});
+
+static ALL_SYLLABLES: LazyLock<HashSet<&'static str>> =
+    LazyLock::new(|| SYLLABLES.values().copied().collect());
+
+static SIMPLIFIED: LazyLock<HashMap<char, char>> = LazyLock::new(|| {
+    T2S.lines()
+        .filter_map(|line| {
+            let mut chars = line.chars();
+            Some((chars.next()?, chars.next()?))
+        })
+        .collect()
+});
+
+/// The most common reading of a Han character, e.g. `zhong` for `中`.
+pub fn pinyin(c: char) -> Option<&'static str> {
+    SYLLABLES.get(&c).copied()
+}
+
+/// Whether `syllable` is the reading of some character.
+pub fn is_syllable(syllable: &str) -> bool {
+    ALL_SYLLABLES.contains(syllable)
+}
+
+/// The Simplified form of a Traditional character, others are kept.
+pub fn simplify(c: char) -> char {
+    SIMPLIFIED.get(&c).copied().unwrap_or(c)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_zh() {
+        assert_eq!(pinyin('中'), Some("zhong"));
+        assert_eq!(pinyin('女'), Some("nv"));
+        assert_eq!(pinyin('a'), None);
+        assert!(is_syllable("zhuang"));
+        assert!(!is_syllable("zhon"));
+        assert_eq!(simplify('學'), '学');
+        assert_eq!(simplify('学'), '学');
+    }
+}
@@ -0,0 +1,384 @@
+mod common;
+
+use std::sync::Arc;
+
+use common::{ACCESS_TOKEN, MockServer, USER_NAME};
+use mmm_memos_lib::{
+    api::models::{
+        Apiv1Memo, Apiv1Shortcut, TheMemoToUpdateTheNameFieldIsRequired, V1Resource, V1State,
+    },
+    client::{MemosClient, auth::AuthFailure, compat::ServerVersion, memo::ListMemosRequest},
+    store::{
+        backend::memory::{MemoryKvStore, MemoryMemoStore},
+        filter::MemoFilter,
+        memo::{FilteredMemoPageRequest, cache_memo, load_memo},
+        model::{AppState, Memo, MemoBase, StoreData},
+        outbox::OutboxOp,
+    },
+    sync::{
+        SERVER_VERSION_KEY,
+        auth::AuthState,
+        outbox::replay_outbox,
+        run_sync,
+        shortcut::{
+            create_shortcut, delete_shortcut, get_shortcut_memo_page, list_shortcuts,
+            update_shortcut,
+        },
+    },
+};
+use reqwest::StatusCode;
+use tauri::{
+    App, Manager,
+    test::{MockRuntime, mock_app},
+};
+
+#[tokio::test]
+async fn test_memo_crud() {
+    let server = MockServer::start().await;
+    let client = MemosClient::new(&server.url, ACCESS_TOKEN).unwrap();
+
+    let memo = Apiv1Memo {
+        content: Some("hello".to_string()),
+        ..Default::default()
+    };
+    let created = client.create_memo(&memo).await.unwrap();
+    let name = created.name.clone().unwrap();
+    assert_eq!(created.content.as_deref(), Some("hello"));
+
+    let update = TheMemoToUpdateTheNameFieldIsRequired {
+        content: Some("hello world".to_string()),
+        pinned: Some(true),
+        ..Default::default()
+    };
+    let updated = client.update_memo(&name, &update).await.unwrap();
+    assert_eq!(updated.content.as_deref(), Some("hello world"));
+    assert_eq!(updated.pinned, Some(true));
+    assert!(updated.update_time > created.update_time);
+
+    let archive = TheMemoToUpdateTheNameFieldIsRequired {
+        state: Some(V1State::Archived),
+        ..Default::default()
+    };
+    client.update_memo(&name, &archive).await.unwrap();
+    let normal = client
+        .list_all_memos(&ListMemosRequest::default())
+        .await
+        .unwrap();
+    assert!(normal.is_empty());
+
+    client.delete_memo(&name).await.unwrap();
+    let e = client.get_memo(&name).await.unwrap_err();
+    assert_eq!(e.status(), Some(StatusCode::NOT_FOUND));
+}
+
+#[tokio::test]
+async fn test_list_all_memos_follows_pages() {
+    let server = MockServer::start().await;
+    for i in 0..5 {
+        server.state().insert_memo(&format!("memo {}", i));
+    }
+
+    let client = MemosClient::new(&server.url, ACCESS_TOKEN).unwrap();
+    let request = ListMemosRequest {
+        page_size: Some(2),
+        ..Default::default()
+    };
+    let memos = client.list_all_memos(&request).await.unwrap();
+
+    assert_eq!(memos.len(), 5);
+    assert_eq!(memos[0].content.as_deref(), Some("memo 4"));
+    assert_eq!(
+        server
+            .state()
+            .requests
+            .iter()
+            .filter(|x| *x == "GET memos")
+            .count(),
+        3
+    );
+}
+
+#[tokio::test]
+async fn test_unauthenticated() {
+    let server = MockServer::start().await;
+    let client = MemosClient::new(&server.url, "expired").unwrap();
+
+    let e = client.get_auth_status().await.unwrap_err();
+    assert_eq!(e.auth_failure(), Some(AuthFailure::Unauthenticated));
+
+    let client = MemosClient::new(&server.url, ACCESS_TOKEN).unwrap();
+    let user = client.get_auth_status().await.unwrap();
+    assert_eq!(user.name.as_deref(), Some(common::USER_NAME));
+}
+
+#[tokio::test]
+async fn test_newer_server_uses_attachments() {
+    let server = MockServer::with_version("0.25.1").await;
+    let client = MemosClient::new(&server.url, ACCESS_TOKEN).unwrap();
+
+    let version = client.probe_server_version().await.unwrap();
+    assert_eq!(version, ServerVersion::new(0, 25, 1));
+
+    // Without the adapter the old paths are gone.
+    let e = client.list_resources().await.unwrap_err();
+    assert_eq!(e.status(), Some(StatusCode::NOT_FOUND));
+
+    let client = client.with_server_version(version);
+    let resource = V1Resource {
+        filename: Some("a.png".to_string()),
+        ..Default::default()
+    };
+    let resource = client.create_resource(&resource).await.unwrap();
+    let memo = Apiv1Memo {
+        content: Some("with attachment".to_string()),
+        resources: Some(vec![resource.clone()]),
+        ..Default::default()
+    };
+    let memo = client.create_memo(&memo).await.unwrap();
+    assert_eq!(memo.resources.as_deref(), Some(&[resource][..]));
+
+    assert_eq!(client.list_resources().await.unwrap().len(), 1);
+    assert!(
+        server
+            .state()
+            .requests
+            .iter()
+            .any(|x| x == "POST attachments")
+    );
+}
+
+/// An app logged in to `server`, keeping its stores in memory.
+fn logged_in_app(server: &MockServer) -> App<MockRuntime> {
+    let store = StoreData::default();
+    *store.server_url.write() = server.url.clone();
+    *store.user_name.write() = USER_NAME.to_string();
+    store
+        .data
+        .insert("accessToken".to_string(), ACCESS_TOKEN.to_string());
+
+    let app = mock_app();
+    app.manage(AppState::new(
+        store,
+        Arc::new(MemoryKvStore::default()),
+        Arc::new(MemoryMemoStore::default()),
+    ));
+    app.manage(AuthState::default());
+    app
+}
+
+fn content(state: &AppState, memo_name: &str) -> Option<String> {
+    load_memo(state, memo_name).unwrap().map(|x| x.content)
+}
+
+#[tokio::test]
+async fn test_sync_pulls_memos() {
+    let server = MockServer::start().await;
+    server.state().insert_memo("a");
+    server.state().insert_memo("b");
+
+    let app = logged_in_app(&server);
+    let state = app.state::<AppState>();
+    let progress = run_sync(app.handle(), false).await.unwrap();
+    assert!(progress.full);
+    assert_eq!((progress.fetched, progress.updated), (2, 2));
+    assert_eq!(content(&state, "memos/1").as_deref(), Some("a"));
+    assert_eq!(
+        state.store.data.get(SERVER_VERSION_KEY).map(|v| v.clone()),
+        Some("0.24.4".to_string())
+    );
+
+    server.state().edit_memo("memos/1", "a2");
+    server.state().insert_memo("c");
+    let progress = run_sync(app.handle(), false).await.unwrap();
+    assert!(!progress.full);
+    assert_eq!(progress.updated, 2);
+    assert_eq!(content(&state, "memos/1").as_deref(), Some("a2"));
+    assert_eq!(content(&state, "memos/3").as_deref(), Some("c"));
+}
+
+#[tokio::test]
+async fn test_replay_outbox_creates_memo() {
+    let server = MockServer::start().await;
+    let app = logged_in_app(&server);
+    let state = app.state::<AppState>();
+
+    let local = "memos/local-1";
+    let memo = Memo {
+        name: local.to_string(),
+        content: "draft".to_string(),
+        ..Default::default()
+    };
+    cache_memo(&state, local.to_string(), memo);
+    state.outbox().push(OutboxOp::Create {
+        memo_name: local.to_string(),
+        content: "draft".to_string(),
+        visibility: "PRIVATE".to_string(),
+        resources: vec![],
+    });
+    state.outbox().push(OutboxOp::Pin {
+        memo_name: local.to_string(),
+        pinned: true,
+    });
+
+    let (replayed, _) = replay_outbox(app.handle()).await.unwrap();
+    assert_eq!(replayed, 2);
+    assert!(state.outbox().front().is_none());
+    assert_eq!(content(&state, local), None);
+    assert_eq!(content(&state, "memos/1").as_deref(), Some("draft"));
+
+    let memo = server.state().memos[0].clone();
+    assert_eq!(memo.content.as_deref(), Some("draft"));
+    assert_eq!(memo.pinned, Some(true));
+}
+
+#[tokio::test]
+async fn test_replay_outbox_merges_remote_edits() {
+    let server = MockServer::start().await;
+    server.state().insert_memo("a\nb\nc");
+    server.state().insert_memo("x");
+    let app = logged_in_app(&server);
+    let state = app.state::<AppState>();
+    run_sync(app.handle(), false).await.unwrap();
+
+    // Both memos are edited offline while another client changes them.
+    for (memo_name, local, remote) in [("memos/1", "a\nb\nC", "A\nb\nc"), ("memos/2", "y", "z")] {
+        let mut memo = load_memo(&state, memo_name).unwrap().unwrap();
+        memo.base = Some(MemoBase::from(&memo));
+        memo.content = local.to_string();
+        cache_memo(&state, memo_name.to_string(), memo);
+        state.outbox().push(OutboxOp::Update {
+            memo_name: memo_name.to_string(),
+            content: Some(local.to_string()),
+            visibility: None,
+        });
+        server.state().edit_memo(memo_name, remote);
+    }
+    state.outbox().push(OutboxOp::Pin {
+        memo_name: "memos/2".to_string(),
+        pinned: true,
+    });
+
+    replay_outbox(app.handle()).await.unwrap();
+    assert_eq!(server.state().memos[0].content.as_deref(), Some("A\nb\nC"));
+
+    // Unmergeable edits wait for the user, with the pin still queued.
+    assert_eq!(server.state().memos[1].content.as_deref(), Some("z"));
+    let data = state.outbox().data.read().clone();
+    assert!(data.entries.is_empty());
+    assert_eq!(data.conflicts.len(), 1);
+    assert!(matches!(
+        data.conflicts[0].pending[..],
+        [OutboxOp::Pin { pinned: true, .. }]
+    ));
+}
+
+#[tokio::test]
+async fn test_replay_outbox_keeps_later_edits() {
+    let server = MockServer::start().await;
+    server.state().insert_memo("a\nb\nc");
+    let app = logged_in_app(&server);
+    let state = app.state::<AppState>();
+    run_sync(app.handle(), false).await.unwrap();
+
+    // The memo is edited again while the first edit is being pushed.
+    let mut memo = load_memo(&state, "memos/1").unwrap().unwrap();
+    memo.base = Some(MemoBase::from(&memo));
+    for content in ["a\nB\nc", "a\nB2\nc"] {
+        memo.content = content.to_string();
+        cache_memo(&state, "memos/1".to_string(), memo.clone());
+        state.outbox().push(OutboxOp::Update {
+            memo_name: "memos/1".to_string(),
+            content: Some(content.to_string()),
+            visibility: None,
+        });
+        state.outbox().begin();
+    }
+
+    let (replayed, _) = replay_outbox(app.handle()).await.unwrap();
+    assert_eq!(replayed, 2);
+    assert!(state.outbox().data.read().conflicts.is_empty());
+    assert_eq!(server.state().memos[0].content.as_deref(), Some("a\nB2\nc"));
+}
+
+#[tokio::test]
+async fn test_shortcut_crud() {
+    let server = MockServer::start().await;
+    let app = logged_in_app(&server);
+    let handle = app.handle();
+
+    let created = create_shortcut(
+        handle.clone(),
+        "Work".to_string(),
+        r#"tag in ["work"]"#.to_string(),
+    )
+    .await
+    .unwrap();
+    let id = created.id.clone().unwrap();
+    let updated = update_shortcut(handle.clone(), id.clone(), Some("Office".to_string()), None)
+        .await
+        .unwrap();
+    assert_eq!(updated.title.as_deref(), Some("Office"));
+    assert_eq!(updated.filter, created.filter);
+    assert_eq!(server.state().shortcuts, std::slice::from_ref(&updated));
+
+    // Listing replaces the cached shortcuts with the server's.
+    let other = Apiv1Shortcut {
+        id: Some("other".to_string()),
+        title: Some("Pinned".to_string()),
+        filter: Some("pinned".to_string()),
+    };
+    server.state().shortcuts.push(other.clone());
+    let shortcuts = list_shortcuts(handle.clone()).await.unwrap();
+    assert_eq!(shortcuts, [updated, other.clone()]);
+
+    delete_shortcut(handle.clone(), id.clone()).await.unwrap();
+    assert_eq!(server.state().shortcuts, [other]);
+
+    // The cache followed, even with the server gone.
+    server.state().access_token = "revoked".to_string();
+    let shortcuts = list_shortcuts(handle.clone()).await.unwrap();
+    assert_eq!(shortcuts.len(), 1);
+    assert_ne!(shortcuts[0].id.as_ref(), Some(&id));
+}
+
+#[tokio::test]
+async fn test_shortcut_memo_page() {
+    let server = MockServer::start().await;
+    server.state().insert_memo("buy milk");
+    server.state().insert_memo("buy bread");
+    server.state().insert_memo("milk tea");
+    let app = logged_in_app(&server);
+    run_sync(app.handle(), false).await.unwrap();
+    let shortcut = create_shortcut(
+        app.handle().clone(),
+        "Milk".to_string(),
+        "content.contains('milk')".to_string(),
+    )
+    .await
+    .unwrap();
+
+    // Pages come from the cache, the server is not asked.
+    server.state().access_token = "revoked".to_string();
+    let request = |cursor| FilteredMemoPageRequest {
+        filter: MemoFilter::default(),
+        pinned_first: false,
+        limit: 1,
+        sort: Default::default(),
+        cursor,
+    };
+    let id = shortcut.id.unwrap();
+    let page = get_shortcut_memo_page(app.state(), id.clone(), request(None))
+        .await
+        .unwrap();
+    assert_eq!(page.memos[0].content, "milk tea");
+    let page = get_shortcut_memo_page(app.state(), id, request(page.next_cursor))
+        .await
+        .unwrap();
+    assert_eq!(page.memos[0].content, "buy milk");
+    assert_eq!(page.next_cursor, None);
+
+    let e = get_shortcut_memo_page(app.state(), "unknown".to_string(), request(None))
+        .await
+        .unwrap_err();
+    assert_eq!(e, "Shortcut not found: unknown");
+}
@@ -0,0 +1,489 @@
+//! In-process fake of the Memos v1 HTTP API for integration tests.
+//!
+//! Serves memos, resources, auth status and the workspace profile from memory
+//! using the generated models. Only what the app uses is implemented, filters
+//! are ignored.
+
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+};
+
+use chrono::{DateTime, Duration, SecondsFormat, Utc};
+use mmm_memos_lib::{
+    api::models::{
+        Apiv1Memo, Apiv1Shortcut, GooglerpcStatus, V1Resource, V1State, V1User, V1Visibility,
+        V1WorkspaceProfile,
+    },
+    client::compat::ServerVersion,
+};
+use serde::Serialize;
+use serde_json::{Value, json};
+use tokio::{
+    io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader},
+    net::{TcpListener, TcpStream},
+};
+
+pub const ACCESS_TOKEN: &str = "mock-token";
+pub const USER_NAME: &str = "users/1";
+
+#[derive(Debug)]
+pub struct MockState {
+    pub version: String,
+    pub access_token: String,
+    pub memos: Vec<Apiv1Memo>,
+    pub resources: Vec<V1Resource>,
+    pub shortcuts: Vec<Apiv1Shortcut>,
+    /// `METHOD path` of every request, in order.
+    pub requests: Vec<String>,
+    next_id: u32,
+    clock: DateTime<Utc>,
+}
+
+impl MockState {
+    /// Every call is one second later, so update times are strictly ordered.
+    fn now(&mut self) -> String {
+        self.clock += Duration::seconds(1);
+        self.clock.to_rfc3339_opts(SecondsFormat::Secs, true)
+    }
+
+    fn next_id(&mut self) -> u32 {
+        self.next_id += 1;
+        self.next_id
+    }
+
+    /// Servers since 0.25 call resources attachments, in paths, names and
+    /// memo fields. The old names are gone.
+    fn resource_collection(&self) -> &'static str {
+        if ServerVersion::parse(&self.version).is_some_and(|v| v >= ServerVersion::new(0, 25, 0)) {
+            "attachments"
+        } else {
+            "resources"
+        }
+    }
+
+    /// A memo in the server's wire format.
+    fn memo_json(&self, memo: &Apiv1Memo) -> Value {
+        let mut value = serde_json::to_value(memo).unwrap();
+        let map = value.as_object_mut().unwrap();
+        if let Some(resources) = map.remove("resources") {
+            map.insert(self.resource_collection().to_string(), resources);
+        }
+        value
+    }
+
+    /// A memo from the server's wire format, a resource field under the other
+    /// name is unknown to the server and dropped.
+    fn parse_memo(&self, mut value: Value) -> Result<Apiv1Memo, serde_json::Error> {
+        if let Some(map) = value.as_object_mut() {
+            let resources = map.remove(self.resource_collection());
+            map.remove("resources");
+            map.remove("attachments");
+            if let Some(resources) = resources {
+                map.insert("resources".to_string(), resources);
+            }
+        }
+        serde_json::from_value(value)
+    }
+
+    /// Adds a memo as if another client created it.
+    pub fn insert_memo(&mut self, content: &str) -> Apiv1Memo {
+        let id = self.next_id();
+        let now = self.now();
+        let memo = Apiv1Memo {
+            name: Some(format!("memos/{}", id)),
+            state: Some(V1State::Normal),
+            creator: Some(USER_NAME.to_string()),
+            create_time: Some(now.clone()),
+            update_time: Some(now.clone()),
+            display_time: Some(now),
+            content: Some(content.to_string()),
+            visibility: Some(V1Visibility::Private),
+            tags: Some(Vec::new()),
+            pinned: Some(false),
+            resources: Some(Vec::new()),
+            relations: Some(Vec::new()),
+            reactions: Some(Vec::new()),
+            ..Default::default()
+        };
+        self.memos.push(memo.clone());
+        memo
+    }
+
+    /// Changes a memo as if another client edited it.
+    pub fn edit_memo(&mut self, name: &str, content: &str) {
+        let now = self.now();
+        if let Some(memo) = self
+            .memos
+            .iter_mut()
+            .find(|x| x.name.as_deref() == Some(name))
+        {
+            memo.content = Some(content.to_string());
+            memo.update_time = Some(now);
+        }
+    }
+}
+
+pub struct MockServer {
+    pub url: String,
+    pub state: Arc<Mutex<MockState>>,
+}
+
+impl MockServer {
+    pub async fn start() -> Self {
+        Self::with_version("0.24.4").await
+    }
+
+    pub async fn with_version(version: &str) -> Self {
+        let state = Arc::new(Mutex::new(MockState {
+            version: version.to_string(),
+            access_token: ACCESS_TOKEN.to_string(),
+            memos: Vec::new(),
+            resources: Vec::new(),
+            shortcuts: Vec::new(),
+            requests: Vec::new(),
+            next_id: 0,
+            clock: DateTime::from_timestamp(1_735_689_600, 0).unwrap(),
+        }));
+
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+
+        let server_state = state.clone();
+        tokio::spawn(async move {
+            loop {
+                let Ok((stream, _)) = listener.accept().await else {
+                    break;
+                };
+                let state = server_state.clone();
+                tokio::spawn(async move {
+                    if let Err(e) = serve(stream, state).await {
+                        log::warn!("Mock server connection failed: {}", e);
+                    }
+                });
+            }
+        });
+
+        Self { url, state }
+    }
+
+    pub fn state(&self) -> std::sync::MutexGuard<'_, MockState> {
+        self.state.lock().unwrap()
+    }
+}
+
+struct Request {
+    method: String,
+    path: String,
+    query: HashMap<String, String>,
+    token: Option<String>,
+    body: Value,
+}
+
+struct Response {
+    code: u16,
+    body: Value,
+}
+
+impl Response {
+    fn ok<T: Serialize>(body: &T) -> Self {
+        Self {
+            code: 200,
+            body: serde_json::to_value(body).unwrap(),
+        }
+    }
+
+    fn error(code: u16, message: &str) -> Self {
+        let status = GooglerpcStatus {
+            code: Some(code as i32),
+            message: Some(message.to_string()),
+            details: None,
+        };
+        Self {
+            code,
+            body: serde_json::to_value(status).unwrap(),
+        }
+    }
+}
+
+async fn serve(stream: TcpStream, state: Arc<Mutex<MockState>>) -> std::io::Result<()> {
+    let mut reader = BufReader::new(stream);
+
+    let mut line = String::new();
+    reader.read_line(&mut line).await?;
+    let mut parts = line.split_whitespace();
+    let method = parts.next().unwrap_or_default().to_string();
+    let target = parts.next().unwrap_or_default().to_string();
+
+    let mut content_length = 0;
+    let mut token = None;
+    loop {
+        let mut header = String::new();
+        reader.read_line(&mut header).await?;
+        let header = header.trim_end();
+        if header.is_empty() {
+            break;
+        }
+        let Some((name, value)) = header.split_once(':') else {
+            continue;
+        };
+        let value = value.trim();
+        match name.to_ascii_lowercase().as_str() {
+            "content-length" => content_length = value.parse().unwrap_or(0),
+            "authorization" => token = value.strip_prefix("Bearer ").map(str::to_string),
+            _ => {}
+        }
+    }
+
+    let mut body = vec![0; content_length];
+    reader.read_exact(&mut body).await?;
+
+    let url = url::Url::parse(&format!("http://mock{}", target)).unwrap();
+    let request = Request {
+        method,
+        path: url
+            .path()
+            .trim_start_matches("/api/v1/")
+            .trim_end_matches('/')
+            .to_string(),
+        query: url.query_pairs().into_owned().collect(),
+        token,
+        body: serde_json::from_slice(&body).unwrap_or(Value::Null),
+    };
+
+    let response = {
+        let mut state = state.lock().unwrap();
+        state
+            .requests
+            .push(format!("{} {}", request.method, request.path));
+        handle(&mut state, request)
+    };
+
+    let body = serde_json::to_vec(&response.body).unwrap();
+    let head = format!(
+        "HTTP/1.1 {} Mock\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+        response.code,
+        body.len()
+    );
+    let mut stream = reader.into_inner();
+    stream.write_all(head.as_bytes()).await?;
+    stream.write_all(&body).await?;
+    stream.shutdown().await
+}
+
+fn handle(state: &mut MockState, request: Request) -> Response {
+    if request.token.as_deref() != Some(state.access_token.as_str()) {
+        return Response::error(401, "unauthenticated");
+    }
+
+    let collection = state.resource_collection();
+    let segments = request.path.split('/').collect::<Vec<_>>();
+    match (request.method.as_str(), segments.as_slice()) {
+        ("POST", ["auth", "status"]) => Response::ok(&V1User {
+            name: Some(USER_NAME.to_string()),
+            username: Some("mock".to_string()),
+            ..Default::default()
+        }),
+        ("GET", ["workspace", "profile"]) => Response::ok(&V1WorkspaceProfile {
+            owner: Some(USER_NAME.to_string()),
+            version: Some(state.version.clone()),
+            ..Default::default()
+        }),
+        ("GET", ["memos"]) => list_memos(state, &request),
+        ("POST", ["memos"]) => {
+            let memo = match state.parse_memo(request.body) {
+                Ok(memo) => memo,
+                Err(e) => return Response::error(400, &e.to_string()),
+            };
+            let mut created = state.insert_memo(memo.content.as_deref().unwrap_or_default());
+            created.visibility = memo.visibility.or(created.visibility);
+            created.resources = memo.resources.or(created.resources);
+            *state.memos.last_mut().unwrap() = created.clone();
+            Response::ok(&state.memo_json(&created))
+        }
+        ("GET", ["memos", id]) => match find_memo(state, id) {
+            Some(i) => Response::ok(&state.memo_json(&state.memos[i])),
+            None => Response::error(404, "memo not found"),
+        },
+        ("PATCH", ["memos", id]) => {
+            let Some(i) = find_memo(state, id) else {
+                return Response::error(404, "memo not found");
+            };
+            let now = state.now();
+            let mut value = state.memo_json(&state.memos[i]);
+            if let (Some(value), Some(update)) = (value.as_object_mut(), request.body.as_object()) {
+                for (k, v) in update {
+                    if k != "name" {
+                        value.insert(k.clone(), v.clone());
+                    }
+                }
+            }
+            let mut memo = match state.parse_memo(value) {
+                Ok(memo) => memo,
+                Err(e) => return Response::error(400, &e.to_string()),
+            };
+            memo.update_time = Some(now);
+            state.memos[i] = memo;
+            Response::ok(&state.memo_json(&state.memos[i]))
+        }
+        ("DELETE", ["memos", id]) => match find_memo(state, id) {
+            Some(i) => {
+                state.memos.remove(i);
+                Response::ok(&serde_json::Map::new())
+            }
+            None => Response::error(404, "memo not found"),
+        },
+        ("GET", ["memos", id, x]) if *x == collection => match find_memo(state, id) {
+            Some(i) => Response::ok(&json!({ collection: state.memos[i].resources })),
+            None => Response::error(404, "memo not found"),
+        },
+        ("GET", [x]) if *x == collection => Response::ok(&json!({ collection: state.resources })),
+        ("POST", [x]) if *x == collection => {
+            let mut resource: V1Resource = match serde_json::from_value(request.body) {
+                Ok(resource) => resource,
+                Err(e) => return Response::error(400, &e.to_string()),
+            };
+            let id = state.next_id();
+            resource.name = Some(format!("{}/{}", collection, id));
+            resource.create_time = Some(state.now());
+            resource.size = resource.content.as_ref().map(|x| x.len().to_string());
+            resource.content = None;
+            state.resources.push(resource.clone());
+            Response::ok(&resource)
+        }
+        ("GET", [x, id]) if *x == collection => {
+            let name = format!("{}/{}", collection, id);
+            match state
+                .resources
+                .iter()
+                .find(|x| x.name.as_deref() == Some(&name))
+            {
+                Some(resource) => Response::ok(resource),
+                None => Response::error(404, "resource not found"),
+            }
+        }
+        ("DELETE", [x, id]) if *x == collection => {
+            let name = format!("{}/{}", collection, id);
+            let len = state.resources.len();
+            state.resources.retain(|x| x.name.as_deref() != Some(&name));
+            if state.resources.len() == len {
+                Response::error(404, "resource not found")
+            } else {
+                Response::ok(&serde_json::Map::new())
+            }
+        }
+        ("GET", ["users", _, "shortcuts"]) => {
+            Response::ok(&json!({ "shortcuts": state.shortcuts }))
+        }
+        ("POST", ["users", _, "shortcuts"]) => {
+            let mut shortcut: Apiv1Shortcut = match serde_json::from_value(request.body) {
+                Ok(shortcut) => shortcut,
+                Err(e) => return Response::error(400, &e.to_string()),
+            };
+            shortcut.id = Some(state.next_id().to_string());
+            state.shortcuts.push(shortcut.clone());
+            Response::ok(&shortcut)
+        }
+        ("PATCH", ["users", _, "shortcuts", id]) => {
+            let Some(shortcut) = state
+                .shortcuts
+                .iter_mut()
+                .find(|x| x.id.as_deref() == Some(id))
+            else {
+                return Response::error(404, "shortcut not found");
+            };
+            if let Some(title) = request.body["title"].as_str() {
+                shortcut.title = Some(title.to_string());
+            }
+            if let Some(filter) = request.body["filter"].as_str() {
+                shortcut.filter = Some(filter.to_string());
+            }
+            Response::ok(&*shortcut)
+        }
+        ("DELETE", ["users", _, "shortcuts", id]) => {
+            let len = state.shortcuts.len();
+            state.shortcuts.retain(|x| x.id.as_deref() != Some(id));
+            if state.shortcuts.len() == len {
+                Response::error(404, "shortcut not found")
+            } else {
+                Response::ok(&serde_json::Map::new())
+            }
+        }
+        _ => Response::error(404, "not found"),
+    }
+}
+
+fn find_memo(state: &MockState, id: &str) -> Option<usize> {
+    let name = format!("memos/{}", id);
+    state
+        .memos
+        .iter()
+        .position(|x| x.name.as_deref() == Some(&name))
+}
+
+fn list_memos(state: &MockState, request: &Request) -> Response {
+    let memo_state = request
+        .query
+        .get("state")
+        .map(|x| x.as_str())
+        .unwrap_or("NORMAL");
+    let mut memos = state
+        .memos
+        .iter()
+        .filter(|x| serde_json::to_value(x.state.unwrap_or(V1State::Normal)).unwrap() == memo_state)
+        .cloned()
+        .collect::<Vec<_>>();
+
+    // 0.24 takes `sort` and `direction`, newer servers `orderBy`.
+    let (sort, asc) = match request.query.get("orderBy") {
+        Some(order_by) => {
+            let (sort, direction) = order_by.split_once(' ').unwrap_or((order_by, "desc"));
+            (sort.to_string(), direction == "asc")
+        }
+        None => (
+            request
+                .query
+                .get("sort")
+                .cloned()
+                .unwrap_or_else(|| "display_time".to_string()),
+            request.query.get("direction").map(|x| x.as_str()) == Some("ASC"),
+        ),
+    };
+    memos.sort_by(|a, b| {
+        let key = |x: &Apiv1Memo| match sort.as_str() {
+            "update_time" => x.update_time.clone(),
+            "create_time" => x.create_time.clone(),
+            _ => x.display_time.clone(),
+        };
+        if asc {
+            key(a).cmp(&key(b))
+        } else {
+            key(b).cmp(&key(a))
+        }
+    });
+
+    let offset = request
+        .query
+        .get("pageToken")
+        .and_then(|x| x.parse::<usize>().ok())
+        .unwrap_or(0);
+    let page_size = request
+        .query
+        .get("pageSize")
+        .and_then(|x| x.parse::<usize>().ok())
+        .filter(|x| *x > 0)
+        .unwrap_or(10);
+
+    let next_page_token = if offset + page_size < memos.len() {
+        (offset + page_size).to_string()
+    } else {
+        String::new()
+    };
+    let memos = memos
+        .iter()
+        .skip(offset)
+        .take(page_size)
+        .map(|x| state.memo_json(x))
+        .collect::<Vec<_>>();
+    Response::ok(&json!({ "memos": memos, "nextPageToken": next_page_token }))
+}
@@ -115,6 +115,10 @@ export const useAuthStore = defineStore('auth', () => {
                 TauriStore.setItem('serverUrl', serverUrl),
                 TauriStore.setItem('accessToken', accessToken),
             ])
+            // not awaited, a failed probe falls back to the 0.24 api
+            invoke('detect_server_version').catch((error) =>
+                console.warn('Failed to detect server version:', error)
+            )
         } catch (error) {
             console.error('Failed to persist auth state:', error)
             throw error